@@ -0,0 +1,170 @@
+use std::hint::black_box;
+
+use collision_simulation::{
+    collision::grid::Grid,
+    entity::wall::Wall,
+    traits::{Collision, Sides},
+};
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion};
+use raylib::math::{Rectangle, Vector2};
+
+// Same world as the simulation binary, the spacing is twice the smallest block size
+const WIDTH: f32 = 1440.;
+const HEIGHT: f32 = 810.;
+const SPACING: f32 = 30.;
+
+// Object counts, and block sizes spanning one to several cells
+const COUNTS: [usize; 3] = [250, 1000, 4000];
+const SIZES: [f32; 2] = [15., 60.];
+
+// Linear congruential generator, the benchmarks always see the same blocks
+fn rectangles(count: usize, size: f32) -> Vec<Rectangle> {
+    let mut state = 0x2545_f491_4f6c_dd1d_u64;
+    let mut next = |max: f32| {
+        state = state
+            .wrapping_mul(6_364_136_223_846_793_005)
+            .wrapping_add(1_442_695_040_888_963_407);
+        #[allow(clippy::cast_precision_loss)]
+        let unit = (state >> 40) as f32 / (1 << 24) as f32;
+        unit * max
+    };
+
+    (0..count)
+        .map(|_| Rectangle::new(next(WIDTH - size), next(HEIGHT - size), size, size))
+        .collect()
+}
+
+#[allow(clippy::cast_possible_truncation)]
+#[allow(clippy::cast_sign_loss)]
+fn grid() -> Grid<usize> {
+    Grid::new(
+        (HEIGHT / SPACING).ceil() as usize,
+        (WIDTH / SPACING).ceil() as usize,
+        SPACING,
+    )
+}
+
+fn filled_grid(rectangles: &[Rectangle]) -> Grid<usize> {
+    let mut grid = grid();
+    for (index, rec) in rectangles.iter().enumerate() {
+        let (rows, cols) = rec.calculate_grid_ranges(SPACING);
+        grid.set_many(index, rows, cols);
+    }
+    grid
+}
+
+fn set_many(c: &mut Criterion) {
+    let mut group = c.benchmark_group("grid/set_many");
+    for size in SIZES {
+        for count in COUNTS {
+            let rectangles = rectangles(count, size);
+            let ranges = rectangles
+                .iter()
+                .map(|rec| rec.calculate_grid_ranges(SPACING))
+                .collect::<Vec<_>>();
+
+            group.bench_function(BenchmarkId::new(format!("size {size}"), count), |b| {
+                b.iter_batched_ref(
+                    grid,
+                    |grid| {
+                        for (index, (rows, cols)) in ranges.iter().enumerate() {
+                            grid.set_many(index, rows.clone(), cols.clone());
+                        }
+                    },
+                    BatchSize::SmallInput,
+                );
+            });
+        }
+    }
+    group.finish();
+}
+
+// Queries the cells around every block, the way blocks look for their candidates
+fn get_many_unique(c: &mut Criterion) {
+    let mut group = c.benchmark_group("grid/get_many_unique");
+    for size in SIZES {
+        for count in COUNTS {
+            let rectangles = rectangles(count, size);
+            let grid = filled_grid(&rectangles);
+            let ranges = rectangles
+                .iter()
+                .map(|rec| rec.calculate_neighbourhood(SPACING))
+                .collect::<Vec<_>>();
+
+            group.bench_function(BenchmarkId::new(format!("size {size}"), count), |b| {
+                b.iter(|| {
+                    ranges
+                        .iter()
+                        .map(|(rows, cols)| grid.get_many_unique(rows.clone(), cols.clone()).len())
+                        .sum::<usize>()
+                });
+            });
+        }
+    }
+    group.finish();
+}
+
+fn aabb(c: &mut Criterion) {
+    let mut group = c.benchmark_group("sides/aabb");
+    let rec = Rectangle::new(100., 200., 15., 15.);
+    let wall = Wall::new(Vector2::new(10., 20.), Vector2::new(400., 300.));
+
+    group.bench_function("rectangle", |b| b.iter(|| black_box(&rec).aabb()));
+    group.bench_function("wall", |b| b.iter(|| black_box(&wall).aabb()));
+    group.finish();
+}
+
+fn check_collision(c: &mut Criterion) {
+    let mut group = c.benchmark_group("collision/check_collision");
+    let rec = Rectangle::new(100., 100., 15., 15.);
+    let overlapping = Rectangle::new(110., 105., 15., 15.);
+    let apart = Rectangle::new(140., 100., 15., 15.);
+    let wall = Wall::new(Vector2::new(90., 110.), Vector2::new(200., 110.));
+
+    group.bench_function("rectangle overlapping", |b| {
+        b.iter(|| black_box(&rec).check_collision(black_box(&overlapping)));
+    });
+    group.bench_function("rectangle apart", |b| {
+        b.iter(|| black_box(&rec).check_collision(black_box(&apart)));
+    });
+    group.bench_function("wall", |b| {
+        b.iter(|| black_box(&rec).check_collision(black_box(&wall)));
+    });
+
+    // Every block against the others sharing its cells, the narrow phase of a whole step
+    for size in SIZES {
+        for count in COUNTS {
+            let rectangles = rectangles(count, size);
+            let grid = filled_grid(&rectangles);
+            let pairs = rectangles
+                .iter()
+                .enumerate()
+                .flat_map(|(index, rec)| {
+                    let (rows, cols) = rec.calculate_grid_ranges(SPACING);
+                    grid.get_many_unique(rows, cols)
+                        .into_iter()
+                        .filter(move |&&other| other != index)
+                        .map(move |&other| (index, other))
+                })
+                .collect::<Vec<_>>();
+
+            group.bench_function(BenchmarkId::new(format!("size {size}"), count), |b| {
+                b.iter(|| {
+                    pairs
+                        .iter()
+                        .filter(|&&(index, other)| {
+                            rectangles[index]
+                                .check_collision(&rectangles[other])
+                                .into_option()
+                                .is_some()
+                        })
+                        .count()
+                });
+            });
+        }
+    }
+    group.finish();
+}
+
+criterion_group!(benches, set_many, get_many_unique, aabb, check_collision);
+criterion_main!(benches);
@@ -0,0 +1,268 @@
+use std::ops::{Index, IndexMut};
+
+// The generation changes every time the slot is freed, so handles to removed values stop working
+// even after the slot is reused.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Handle {
+    index: usize,
+    generation: u32,
+}
+
+impl Handle {
+    #[must_use]
+    pub fn index(self) -> usize {
+        self.index
+    }
+
+    #[must_use]
+    pub fn generation(self) -> u32 {
+        self.generation
+    }
+}
+
+#[derive(Debug)]
+struct Slot<T> {
+    generation: u32,
+    value: Option<T>,
+}
+
+impl<T: Clone> Clone for Slot<T> {
+    fn clone(&self) -> Self {
+        Self {
+            generation: self.generation,
+            value: self.value.clone(),
+        }
+    }
+
+    fn clone_from(&mut self, source: &Self) {
+        self.generation = source.generation;
+        self.value.clone_from(&source.value);
+    }
+}
+
+#[derive(Debug)]
+pub struct Arena<T> {
+    slots: Vec<Slot<T>>,
+    free: Vec<usize>,
+    len: usize,
+}
+
+// `clone_from` keeps the allocations, copying a world every frame doesn't allocate
+impl<T: Clone> Clone for Arena<T> {
+    fn clone(&self) -> Self {
+        Self {
+            slots: self.slots.clone(),
+            free: self.free.clone(),
+            len: self.len,
+        }
+    }
+
+    fn clone_from(&mut self, source: &Self) {
+        self.slots.clone_from(&source.slots);
+        self.free.clone_from(&source.free);
+        self.len = source.len;
+    }
+}
+
+impl<T> Default for Arena<T> {
+    fn default() -> Self {
+        Self {
+            slots: vec![],
+            free: vec![],
+            len: 0,
+        }
+    }
+}
+
+impl<T> Arena<T> {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn insert(&mut self, value: T) -> Handle {
+        self.insert_with(|_| value)
+    }
+
+    // For values that need to know their own handle
+    pub fn insert_with<F: FnOnce(Handle) -> T>(&mut self, create: F) -> Handle {
+        let index = self.free.pop().unwrap_or_else(|| {
+            self.slots.push(Slot {
+                generation: 0,
+                value: None,
+            });
+            self.slots.len() - 1
+        });
+
+        let slot = &mut self.slots[index];
+        let handle = Handle {
+            index,
+            generation: slot.generation,
+        };
+        slot.value = Some(create(handle));
+        self.len += 1;
+        handle
+    }
+
+    pub fn remove(&mut self, handle: Handle) -> Option<T> {
+        let slot = self
+            .slots
+            .get_mut(handle.index)
+            .filter(|slot| slot.generation == handle.generation)?;
+        let value = slot.value.take()?;
+
+        slot.generation = slot.generation.wrapping_add(1);
+        self.free.push(handle.index);
+        self.len -= 1;
+        Some(value)
+    }
+
+    #[must_use]
+    pub fn contains(&self, handle: Handle) -> bool {
+        self.get(handle).is_some()
+    }
+
+    #[must_use]
+    pub fn get(&self, handle: Handle) -> Option<&T> {
+        self.slots
+            .get(handle.index)
+            .filter(|slot| slot.generation == handle.generation)?
+            .value
+            .as_ref()
+    }
+
+    pub fn get_mut(&mut self, handle: Handle) -> Option<&mut T> {
+        self.slots
+            .get_mut(handle.index)
+            .filter(|slot| slot.generation == handle.generation)?
+            .value
+            .as_mut()
+    }
+
+    // Both values at once, `None` if any of them is missing or both handles are the same
+    pub fn get_pair_mut(&mut self, a: Handle, b: Handle) -> Option<(&mut T, &mut T)> {
+        if a.index == b.index || !self.contains(a) || !self.contains(b) {
+            return None;
+        }
+
+        let (first, second) = if a.index < b.index {
+            let (head, tail) = self.slots.split_at_mut(b.index);
+            (&mut head[a.index], &mut tail[0])
+        } else {
+            let (head, tail) = self.slots.split_at_mut(a.index);
+            (&mut tail[0], &mut head[b.index])
+        };
+
+        Some((first.value.as_mut()?, second.value.as_mut()?))
+    }
+
+    pub fn handles(&self) -> impl Iterator<Item = Handle> + '_ {
+        self.iter().map(|(handle, _)| handle)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (Handle, &T)> {
+        self.slots.iter().enumerate().filter_map(|(index, slot)| {
+            let handle = Handle {
+                index,
+                generation: slot.generation,
+            };
+            slot.value.as_ref().map(|value| (handle, value))
+        })
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (Handle, &mut T)> {
+        self.slots
+            .iter_mut()
+            .enumerate()
+            .filter_map(|(index, slot)| {
+                let handle = Handle {
+                    index,
+                    generation: slot.generation,
+                };
+                slot.value.as_mut().map(|value| (handle, value))
+            })
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = &T> {
+        self.slots.iter().filter_map(|slot| slot.value.as_ref())
+    }
+
+    pub fn values_mut(&mut self) -> impl Iterator<Item = &mut T> {
+        self.slots.iter_mut().filter_map(|slot| slot.value.as_mut())
+    }
+}
+
+impl<T> Index<Handle> for Arena<T> {
+    type Output = T;
+
+    fn index(&self, handle: Handle) -> &Self::Output {
+        self.get(handle).expect("stale or invalid handle")
+    }
+}
+
+impl<T> IndexMut<Handle> for Arena<T> {
+    fn index_mut(&mut self, handle: Handle) -> &mut Self::Output {
+        self.get_mut(handle).expect("stale or invalid handle")
+    }
+}
+
+impl<T> FromIterator<T> for Arena<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut arena = Self::new();
+        iter.into_iter().for_each(|value| {
+            arena.insert(value);
+        });
+        arena
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stale_handle() {
+        let mut arena = Arena::new();
+        let first = arena.insert("first");
+        let second = arena.insert("second");
+
+        assert_eq!(arena.remove(first), Some("first"));
+        assert_eq!(arena.remove(first), None);
+        assert_eq!(arena.get(first), None);
+
+        // The slot is reused with a new generation
+        let third = arena.insert("third");
+        assert_eq!(third.index(), first.index());
+        assert_ne!(third, first);
+        assert_eq!(arena.get(first), None);
+        assert_eq!(arena[third], "third");
+        assert_eq!(arena.len(), 2);
+        assert_eq!(
+            arena.values().copied().collect::<Vec<_>>(),
+            ["third", "second"]
+        );
+        assert_eq!(arena.handles().collect::<Vec<_>>(), [third, second]);
+    }
+
+    #[test]
+    fn test_pair() {
+        let mut arena = Arena::new();
+        let a = arena.insert(1);
+        let b = arena.insert(2);
+
+        assert!(arena.get_pair_mut(a, a).is_none());
+        let (second, first) = arena.get_pair_mut(b, a).unwrap();
+        std::mem::swap(first, second);
+        assert_eq!((arena[a], arena[b]), (2, 1));
+    }
+}
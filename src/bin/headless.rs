@@ -0,0 +1,109 @@
+use std::time::{Duration, Instant};
+
+use clap::{error::ErrorKind, CommandFactory, Parser};
+use collision_simulation::{random_world::RandomWorld, world::StepMetrics};
+
+/// Steps the random world without a window and prints the step metrics as JSON lines
+#[derive(Debug, Parser)]
+#[command(name = "headless", about)]
+struct Options {
+    #[command(flatten)]
+    world: RandomWorld,
+
+    /// Number of steps
+    #[arg(long, default_value_t = 600)]
+    frames: u32,
+
+    /// Seed for the random blocks, the same seed always builds the same world
+    #[arg(long, default_value_t = 0)]
+    seed: u64,
+
+    /// Seconds per step
+    #[arg(long, default_value_t = 1. / 60.)]
+    delta: f32,
+
+    /// Print the metrics of every step before the summary
+    #[arg(long)]
+    per_frame: bool,
+}
+
+// SplitMix64, raylib's generator needs a window
+struct Random(u64);
+
+impl Random {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    // Both ends included, like raylib's `get_random_value`
+    #[allow(clippy::cast_possible_truncation)]
+    #[allow(clippy::cast_possible_wrap)]
+    #[allow(clippy::cast_sign_loss)]
+    fn range(&mut self, min: i32, max: i32) -> i32 {
+        let span = (i64::from(max) - i64::from(min) + 1).max(1) as u64;
+        (i64::from(min) + (self.next() % span) as i64) as i32
+    }
+}
+
+fn main() {
+    let options = Options::parse();
+    if let Err(message) = options.world.validate() {
+        Options::command()
+            .error(ErrorKind::ValueValidation, message)
+            .exit();
+    }
+    let mut random = Random(options.seed);
+    let mut world = options.world.build(|min, max| random.range(min, max));
+
+    let mut total = StepMetrics::default();
+    let start = Instant::now();
+    for frame in 0..options.frames {
+        world.step(options.delta);
+        let metrics = world.metrics();
+        if options.per_frame {
+            println!("{{\"frame\":{frame},{}}}", fields(metrics, 1));
+        }
+
+        total.grid_build += metrics.grid_build;
+        total.broad_phase += metrics.broad_phase;
+        total.narrow_phase += metrics.narrow_phase;
+        total.update += metrics.update;
+        total.candidate_pairs += metrics.candidate_pairs;
+        total.tested_pairs += metrics.tested_pairs;
+        total.collision_checks += metrics.collision_checks;
+    }
+    let elapsed = start.elapsed();
+
+    println!(
+        "{{\"blocks\":{},\"frames\":{},\"seed\":{},\"elapsed_ms\":{:.3},\"mean\":{{{}}}}}",
+        world.blocks().len(),
+        options.frames,
+        options.seed,
+        elapsed.as_secs_f64() * 1e3,
+        fields(&total, options.frames.max(1)),
+    );
+}
+
+// Times in microseconds, averaged over the frames
+#[allow(clippy::cast_precision_loss)]
+fn fields(metrics: &StepMetrics, frames: u32) -> String {
+    let micros = |duration: Duration| duration.as_secs_f64() * 1e6 / f64::from(frames);
+    let count = |count: usize| count as f64 / f64::from(frames);
+    let step = metrics.grid_build + metrics.broad_phase + metrics.narrow_phase + metrics.update;
+
+    format!(
+        "\"grid_build_us\":{:.3},\"broad_phase_us\":{:.3},\"narrow_phase_us\":{:.3},\"update_us\":{:.3},\"step_us\":{:.3},\"candidate_pairs\":{},\"tested_pairs\":{},\"collision_checks\":{}",
+        micros(metrics.grid_build),
+        micros(metrics.broad_phase),
+        micros(metrics.narrow_phase),
+        micros(metrics.update),
+        micros(step),
+        count(metrics.candidate_pairs),
+        count(metrics.tested_pairs),
+        count(metrics.collision_checks),
+    )
+}
@@ -0,0 +1,178 @@
+use std::{collections::HashMap, ops::RangeInclusive};
+
+use crate::{
+    arena::{Arena, Handle},
+    entity::{block::Block, sensor::Sensor, wall::Wall},
+    traits::GridItemTrait,
+};
+
+use super::grid::{Col, Grid, Row};
+
+// What the grid stores, the items themselves are looked up in the world storage
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum GridKey {
+    Wall(usize),
+    Sensor(usize),
+    Block(Handle),
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Bodies<'a> {
+    pub blocks: &'a Arena<Block>,
+    pub walls: &'a [Wall],
+    pub sensors: &'a [Sensor],
+}
+
+impl<'a> Bodies<'a> {
+    #[must_use]
+    pub fn get(&self, key: GridKey) -> Option<&'a dyn GridItemTrait> {
+        match key {
+            GridKey::Wall(index) => self.walls.get(index).map(|wall| wall as &dyn GridItemTrait),
+            GridKey::Sensor(index) => self.sensors.get(index).map(|sensor| sensor as _),
+            GridKey::Block(handle) => self.blocks.get(handle).map(|block| block as _),
+        }
+    }
+
+    // Walls and sensors give `None`
+    #[must_use]
+    pub fn block(&self, key: GridKey) -> Option<&'a Block> {
+        match key {
+            GridKey::Block(handle) => self.blocks.get(handle),
+            GridKey::Wall(_) | GridKey::Sensor(_) => None,
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (GridKey, &'a dyn GridItemTrait)> {
+        let walls = self
+            .walls
+            .iter()
+            .enumerate()
+            .map(|(index, wall)| (GridKey::Wall(index), wall as &dyn GridItemTrait));
+        let sensors = self
+            .sensors
+            .iter()
+            .enumerate()
+            .map(|(index, sensor)| (GridKey::Sensor(index), sensor as _));
+        let blocks = self
+            .blocks
+            .iter()
+            .map(|(handle, block)| (GridKey::Block(handle), block as _));
+
+        walls.chain(sensors).chain(blocks)
+    }
+}
+
+type Ranges = (RangeInclusive<Row>, RangeInclusive<Col>);
+
+// Keeps the grid between steps, only the items changing cells are moved.
+#[derive(Debug)]
+pub struct BroadPhase {
+    grid: Grid<GridKey>,
+    ranges: HashMap<GridKey, Ranges>,
+}
+
+impl BroadPhase {
+    #[must_use]
+    pub fn new(rows: usize, cols: usize, spacing: f32) -> Self {
+        Self {
+            grid: Grid::new(rows, cols, spacing),
+            ranges: HashMap::new(),
+        }
+    }
+
+    #[must_use]
+    pub fn grid(&self) -> &Grid<GridKey> {
+        &self.grid
+    }
+
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.ranges.len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.ranges.is_empty()
+    }
+
+    pub fn update(&mut self, bodies: Bodies) {
+        let removed = self
+            .ranges
+            .keys()
+            .filter(|&&key| bodies.get(key).is_none())
+            .copied()
+            .collect::<Vec<_>>();
+        for key in removed {
+            self.remove(key);
+        }
+
+        for (key, item) in bodies.iter() {
+            self.insert(key, item);
+        }
+    }
+
+    pub fn insert(&mut self, key: GridKey, item: &dyn GridItemTrait) {
+        let ranges = item.calculate_grid_ranges(self.grid.spacing);
+        if self.ranges.get(&key) == Some(&ranges) {
+            return;
+        }
+
+        self.remove(key);
+        self.grid.set_many(key, ranges.0.clone(), ranges.1.clone());
+        self.ranges.insert(key, ranges);
+    }
+
+    pub fn remove(&mut self, key: GridKey) {
+        if let Some((rows, cols)) = self.ranges.remove(&key) {
+            self.grid.remove_many(&key, rows, cols);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use raylib::math::{Rectangle, Vector2};
+
+    use crate::traits::Position;
+
+    use super::*;
+
+    fn bodies<'a>(blocks: &'a Arena<Block>, walls: &'a [Wall]) -> Bodies<'a> {
+        Bodies {
+            blocks,
+            walls,
+            sensors: &[],
+        }
+    }
+
+    #[test]
+    fn test_moves_only_changed_items() {
+        let mut blocks = Arena::new();
+        let block = blocks.insert(Block::new(
+            Rectangle::new(12., 12., 5., 5.),
+            Vector2::zero(),
+        ));
+        let key = GridKey::Block(block);
+        let walls = [Wall::new(Vector2::new(0., 5.), Vector2::new(95., 5.))];
+        let mut broad_phase = BroadPhase::new(10, 10, 10.);
+
+        broad_phase.update(bodies(&blocks, &walls));
+        assert_eq!(broad_phase.len(), 2);
+        assert_eq!(broad_phase.grid().get(&Row(1), &Col(1)), Some(&vec![key]));
+
+        blocks[block].set_position(Vector2::new(52., 32.));
+        broad_phase.update(bodies(&blocks, &walls));
+        assert_eq!(broad_phase.grid().get(&Row(1), &Col(1)), Some(&vec![]));
+        assert_eq!(broad_phase.grid().get(&Row(3), &Col(5)), Some(&vec![key]));
+
+        // Removed bodies leave the grid
+        blocks.remove(block);
+        broad_phase.update(bodies(&blocks, &walls));
+        assert_eq!(broad_phase.len(), 1);
+        assert_eq!(broad_phase.grid().get(&Row(3), &Col(5)), Some(&vec![]));
+        assert_eq!(
+            broad_phase.grid().get(&Row(0), &Col(4)),
+            Some(&vec![GridKey::Wall(0)])
+        );
+    }
+}
@@ -1,26 +1,32 @@
 use raylib::math::Vector2;
 
-#[derive(Debug, Default, Clone)]
-pub struct CollisionResult(pub [Option<Vector2>; 4]);
+use crate::geometry::{Scalar, Vector};
 
+#[derive(Debug, Clone)]
+pub struct CollisionResult<V = Vector2>(pub [Option<V>; 4]);
+
+impl<V> Default for CollisionResult<V> {
+    fn default() -> Self {
+        Self([None, None, None, None])
+    }
+}
+
+// Indices of the sides, the same for every vector type
 impl CollisionResult {
     pub(crate) const TOP: usize = 0;
     pub(crate) const RIGHT: usize = 1;
     pub(crate) const BOTTOM: usize = 2;
     pub(crate) const LEFT: usize = 3;
+}
 
-    pub fn new(
-        top: Option<Vector2>,
-        right: Option<Vector2>,
-        bottom: Option<Vector2>,
-        left: Option<Vector2>,
-    ) -> Self {
+impl<V: Vector> CollisionResult<V> {
+    pub fn new(top: Option<V>, right: Option<V>, bottom: Option<V>, left: Option<V>) -> Self {
         let mut result = [None; 4];
 
-        result[Self::TOP] = top;
-        result[Self::RIGHT] = right;
-        result[Self::BOTTOM] = bottom;
-        result[Self::LEFT] = left;
+        result[CollisionResult::TOP] = top;
+        result[CollisionResult::RIGHT] = right;
+        result[CollisionResult::BOTTOM] = bottom;
+        result[CollisionResult::LEFT] = left;
 
         Self(result)
     }
@@ -32,8 +38,21 @@ impl CollisionResult {
         }
     }
 
+    // Average of the intersection points
+    pub fn center(&self) -> Option<V> {
+        let (sum, count) = self
+            .0
+            .iter()
+            .flatten()
+            .fold((V::zero(), V::Scalar::ZERO), |(sum, count), point| {
+                (sum + *point, count + V::Scalar::ONE)
+            });
+
+        (count > V::Scalar::ZERO).then(|| sum / count)
+    }
+
     #[allow(clippy::needless_pass_by_value)]
-    fn combine(self, other: CollisionResult) -> Self {
+    fn combine(self, other: Self) -> Self {
         let combined = [
             self.0[0].or(other.0[0]),
             self.0[1].or(other.0[1]),
@@ -45,27 +64,22 @@ impl CollisionResult {
     }
 }
 
-impl FromIterator<CollisionResult> for CollisionResult {
-    fn from_iter<T: IntoIterator<Item = CollisionResult>>(iter: T) -> Self {
+impl<V: Vector> FromIterator<CollisionResult<V>> for CollisionResult<V> {
+    fn from_iter<T: IntoIterator<Item = CollisionResult<V>>>(iter: T) -> Self {
         iter.into_iter()
             .reduce(CollisionResult::combine)
             .unwrap_or_default()
     }
 }
 
-impl FromIterator<Option<Vector2>> for CollisionResult {
-    fn from_iter<T: IntoIterator<Item = Option<Vector2>>>(iter: T) -> Self {
-        iter.into_iter()
-            .collect::<Vec<_>>()
-            .chunks(4)
-            .map(|chunk| match *chunk {
-                [] => CollisionResult::default(),
-                [top] => CollisionResult::new(top, None, None, None),
-                [top, right] => CollisionResult::new(top, right, None, None),
-                [top, right, bottom] => CollisionResult::new(top, right, bottom, None),
-                [top, right, bottom, left] => CollisionResult::new(top, right, bottom, left),
-                [..] => unreachable!(),
-            })
-            .collect::<CollisionResult>()
+impl<V: Vector> FromIterator<Option<V>> for CollisionResult<V> {
+    fn from_iter<T: IntoIterator<Item = Option<V>>>(iter: T) -> Self {
+        // Every four points are one result, the first point found for a side is kept
+        let mut result = Self::default();
+        for (index, point) in iter.into_iter().enumerate() {
+            let side = &mut result.0[index % 4];
+            *side = side.or(point);
+        }
+        result
     }
 }
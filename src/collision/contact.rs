@@ -0,0 +1,198 @@
+use raylib::math::Vector2;
+
+use crate::{arena::Handle, geometry::Scalar};
+
+use super::{broad_phase::GridKey, collision_result::CollisionResult, manifold::Manifold};
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Friction<S = f32> {
+    pub static_coefficient: S,
+    pub dynamic_coefficient: S,
+}
+
+impl<S: Scalar> Friction<S> {
+    #[must_use]
+    pub fn new(static_coefficient: S, dynamic_coefficient: S) -> Self {
+        Self {
+            static_coefficient,
+            dynamic_coefficient,
+        }
+    }
+
+    #[must_use]
+    pub fn combine(self, other: Self) -> Self {
+        Self::new(
+            (self.static_coefficient * other.static_coefficient).sqrt(),
+            (self.dynamic_coefficient * other.dynamic_coefficient).sqrt(),
+        )
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Contact {
+    pub other: Option<Handle>,
+    // Grid key of the other body, walls have one too
+    pub key: Option<GridKey>,
+    pub result: CollisionResult,
+    pub manifold: Manifold,
+    // Child shapes touching, always 0 for bodies made of a single one
+    pub shape: usize,
+    pub other_shape: usize,
+    // Speed of the other body where they touch, used when it isn't a block
+    pub other_speed: Vector2,
+    pub restitution: f32,
+    pub friction: Friction,
+    // The body can cross the contact while moving along this direction
+    pub one_way: Option<Vector2>,
+}
+
+impl Contact {
+    #[must_use]
+    pub fn new(other: Option<Handle>, result: CollisionResult, manifold: Manifold) -> Self {
+        Self {
+            other,
+            key: None,
+            result,
+            manifold,
+            shape: 0,
+            other_shape: 0,
+            other_speed: Vector2::zero(),
+            restitution: 1.,
+            friction: Friction::default(),
+            one_way: None,
+        }
+    }
+
+    #[must_use]
+    pub fn is_passing_through(&self, speed: Vector2) -> bool {
+        self.one_way
+            .is_some_and(|direction| speed.dot(direction) > 0.)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContactEvent<K = Handle> {
+    Begin(K, K),
+    Persist(K, K),
+    End(K, K),
+}
+
+#[derive(Debug)]
+pub struct ContactTracker<K = Handle> {
+    touching: Vec<(K, K)>,
+    // Pairs of the update in progress, swapped with `touching` once done
+    pairs: Vec<(K, K)>,
+}
+
+// `clone_from` keeps the allocation of the touching pairs
+impl<K: Clone> Clone for ContactTracker<K> {
+    fn clone(&self) -> Self {
+        Self {
+            touching: self.touching.clone(),
+            pairs: vec![],
+        }
+    }
+
+    fn clone_from(&mut self, source: &Self) {
+        self.touching.clone_from(&source.touching);
+    }
+}
+
+impl<K> Default for ContactTracker<K> {
+    fn default() -> Self {
+        Self {
+            touching: vec![],
+            pairs: vec![],
+        }
+    }
+}
+
+impl<K: Ord + Copy> ContactTracker<K> {
+    #[must_use]
+    pub fn touching(&self) -> &[(K, K)] {
+        &self.touching
+    }
+
+    // Pairs are unordered, (a, b) and (b, a) are the same contact.
+    pub fn update<I: IntoIterator<Item = (K, K)>>(&mut self, pairs: I) -> Vec<ContactEvent<K>> {
+        let mut events = vec![];
+        self.update_keeping(pairs, |_| false, &mut events);
+        events
+    }
+
+    // Same as `update`, touching pairs whose bodies are both kept persist even when not reported.
+    // Sleeping bodies don't look for contacts but still touch the same ones. The events are pushed
+    // to `events`, once the buffers are large enough this doesn't allocate.
+    pub fn update_keeping<I, F>(&mut self, pairs: I, keep: F, events: &mut Vec<ContactEvent<K>>)
+    where
+        I: IntoIterator<Item = (K, K)>,
+        F: Fn(K) -> bool,
+    {
+        self.pairs.clear();
+        self.pairs
+            .extend(pairs.into_iter().map(|(a, b)| (a.min(b), a.max(b))));
+        self.pairs.extend(
+            self.touching
+                .iter()
+                .copied()
+                .filter(|&(a, b)| keep(a) && keep(b)),
+        );
+        self.pairs.sort_unstable();
+        self.pairs.dedup();
+
+        events.extend(self.pairs.iter().map(|&(a, b)| {
+            if self.touching.binary_search(&(a, b)).is_ok() {
+                ContactEvent::Persist(a, b)
+            } else {
+                ContactEvent::Begin(a, b)
+            }
+        }));
+        events.extend(
+            self.touching
+                .iter()
+                .filter(|pair| self.pairs.binary_search(pair).is_err())
+                .map(|&(a, b)| ContactEvent::End(a, b)),
+        );
+        std::mem::swap(&mut self.touching, &mut self.pairs);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_begin_persist_end() {
+        let mut tracker = ContactTracker::default();
+
+        assert_eq!(
+            tracker.update([(3, 1), (1, 3)]),
+            [ContactEvent::Begin(1, 3)]
+        );
+        assert_eq!(
+            tracker.update([(1, 3), (2, 4)]),
+            [ContactEvent::Persist(1, 3), ContactEvent::Begin(2, 4)]
+        );
+        assert_eq!(
+            tracker.update([(4, 2)]),
+            [ContactEvent::Persist(2, 4), ContactEvent::End(1, 3)]
+        );
+        assert_eq!(tracker.update([]), [ContactEvent::End(2, 4)]);
+        assert!(tracker.touching().is_empty());
+    }
+
+    #[test]
+    fn test_kept_pairs_persist() {
+        let mut tracker = ContactTracker::default();
+        tracker.update([(1, 2), (2, 3)]);
+
+        let asleep = |key| key != 3;
+        let mut events = vec![];
+        tracker.update_keeping([], asleep, &mut events);
+        assert_eq!(
+            events,
+            [ContactEvent::Persist(1, 2), ContactEvent::End(2, 3)]
+        );
+        assert_eq!(tracker.touching(), [(1, 2)]);
+    }
+}
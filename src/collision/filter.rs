@@ -0,0 +1,85 @@
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CollisionFilter {
+    pub category: u32,
+    pub mask: u32,
+    pub group: i32,
+}
+
+impl CollisionFilter {
+    pub const ALL: u32 = u32::MAX;
+
+    #[must_use]
+    pub fn new(category: u32, mask: u32) -> Self {
+        Self {
+            category,
+            mask,
+            group: 0,
+        }
+    }
+
+    #[must_use]
+    pub fn with_group(self, group: i32) -> Self {
+        Self { group, ..self }
+    }
+
+    // Same non zero group: positive always collides, negative never does.
+    // Otherwise each category has to be accepted by the other mask.
+    #[must_use]
+    pub fn should_collide(&self, other: &Self) -> bool {
+        if self.group != 0 && self.group == other.group {
+            return self.group > 0;
+        }
+
+        self.category & other.mask != 0 && other.category & self.mask != 0
+    }
+}
+
+impl Default for CollisionFilter {
+    fn default() -> Self {
+        Self::new(1, Self::ALL)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PLAYER: u32 = 1 << 1;
+    const DEBRIS: u32 = 1 << 2;
+    const PROJECTILE: u32 = 1 << 3;
+
+    #[test]
+    fn test_default_collides_with_everything() {
+        let filter = CollisionFilter::default();
+
+        assert!(filter.should_collide(&CollisionFilter::default()));
+        assert!(filter.should_collide(&CollisionFilter::new(DEBRIS, CollisionFilter::ALL)));
+    }
+
+    #[test]
+    fn test_mask_is_checked_both_ways() {
+        let debris = CollisionFilter::new(DEBRIS, CollisionFilter::ALL & !DEBRIS);
+        let player = CollisionFilter::new(PLAYER, CollisionFilter::ALL);
+
+        assert!(!debris.should_collide(&debris));
+        assert!(debris.should_collide(&player));
+        assert!(player.should_collide(&debris));
+
+        let ghost = CollisionFilter::new(PLAYER, 0);
+        assert!(!ghost.should_collide(&player));
+        assert!(!player.should_collide(&ghost));
+    }
+
+    #[test]
+    fn test_group_overrides_mask() {
+        let owner = CollisionFilter::new(PLAYER, CollisionFilter::ALL).with_group(-1);
+        let projectile = CollisionFilter::new(PROJECTILE, CollisionFilter::ALL).with_group(-1);
+        let enemy = CollisionFilter::new(PLAYER, CollisionFilter::ALL).with_group(-2);
+
+        assert!(!owner.should_collide(&projectile));
+        assert!(projectile.should_collide(&enemy));
+
+        let chain = CollisionFilter::new(DEBRIS, 0).with_group(3);
+        assert!(chain.should_collide(&chain));
+    }
+}
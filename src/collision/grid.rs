@@ -12,15 +12,16 @@ pub struct Row(pub usize);
 #[derive(PartialEq, Clone)]
 pub struct Col(pub usize);
 
-pub struct Grid<T> {
+// `S` is the scalar of the item coordinates, the cell size uses the same one.
+pub struct Grid<T, S = f32> {
     data: Vec<Cell<T>>,
     rows: usize,
     cols: usize,
-    pub spacing: f32,
+    pub spacing: S,
 }
 
-impl<T> Grid<T> {
-    pub fn new(rows: usize, cols: usize, spacing: f32) -> Self {
+impl<T, S> Grid<T, S> {
+    pub fn new(rows: usize, cols: usize, spacing: S) -> Self {
         let data = (0..rows * cols).map(|_| Cell::empty()).collect();
 
         Self {
@@ -31,20 +32,41 @@ impl<T> Grid<T> {
         }
     }
 
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    pub fn cols(&self) -> usize {
+        self.cols
+    }
+
+    // Empties every cell, they keep their allocation for the next items
+    pub fn clear(&mut self) {
+        self.data.iter_mut().for_each(|cell| cell.0.clear());
+    }
+
     pub fn get(&self, row: &Row, col: &Col) -> Option<&Vec<T>> {
         self.data.get(self.get_index(row, col)).map(|cell| &cell.0)
     }
 
     pub fn get_many(&self, rows: RangeInclusive<Row>, cols: RangeInclusive<Col>) -> Vec<&T> {
+        self.iter_many(rows, cols).collect()
+    }
+
+    // Same as `get_many` without collecting, items spanning several cells come up once per cell
+    pub fn iter_many(
+        &self,
+        rows: RangeInclusive<Row>,
+        cols: RangeInclusive<Col>,
+    ) -> impl Iterator<Item = &T> {
         let cols = Self::map_range(cols);
         let rows = Self::map_range(rows);
-        cols.flat_map(|col| {
+        cols.flat_map(move |col| {
             rows.clone()
                 .map(move |row| self.get_index(&Row(row), &Col(col)))
         })
         .filter_map(|index| self.data.get(index))
         .flat_map(|cell| &cell.0)
-        .collect::<Vec<_>>()
     }
 
     fn get_index(&self, row: &Row, col: &Col) -> usize {
@@ -74,7 +96,7 @@ impl<T> Grid<T> {
     }
 }
 
-impl<T: Clone> Grid<T> {
+impl<T: Clone, S> Grid<T, S> {
     pub fn set_many(
         &mut self,
         item: T,
@@ -96,17 +118,9 @@ impl<T: Clone> Grid<T> {
         .collect::<Option<_>>()
     }
 }
-impl<T: PartialEq> Grid<T> {
+impl<T: PartialEq, S> Grid<T, S> {
     pub fn get_many_unique(&self, rows: RangeInclusive<Row>, cols: RangeInclusive<Col>) -> Vec<&T> {
-        let cols = Self::map_range(cols);
-        let rows = Self::map_range(rows);
-        cols.flat_map(|col| {
-            rows.clone()
-                .map(move |row| self.get_index(&Row(row), &Col(col)))
-        })
-        .filter_map(|index| self.data.get(index))
-        .flat_map(|cell| &cell.0)
-        .fold(vec![], |mut acc, cur| {
+        self.iter_many(rows, cols).fold(vec![], |mut acc, cur| {
             if !acc.contains(&cur) {
                 acc.push(cur);
             }
@@ -114,6 +128,20 @@ impl<T: PartialEq> Grid<T> {
         })
     }
 
+    pub fn remove_many(&mut self, item: &T, rows: RangeInclusive<Row>, cols: RangeInclusive<Col>) {
+        let cols = Self::map_range(cols);
+        let rows = Self::map_range(rows);
+        let grid_cols = self.cols;
+        for index in cols.flat_map(|col| {
+            rows.clone()
+                .map(move |row| Self::get_vec_index(&Row(row), &Col(col), grid_cols))
+        }) {
+            if let Some(cell) = self.data.get_mut(index) {
+                cell.0.retain(|other| other != item);
+            }
+        }
+    }
+
     pub fn set_unique(&mut self, item: T, row: &Row, col: &Col) -> Option<bool> {
         let index = self.get_index(row, col);
         self.data
@@ -122,7 +150,7 @@ impl<T: PartialEq> Grid<T> {
     }
 }
 
-impl<T: Debug> Debug for Grid<T> {
+impl<T: Debug, S: Debug> Debug for Grid<T, S> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         writeln!(f)?;
         writeln!(f, "cols: {}", self.cols)?;
@@ -300,4 +328,28 @@ mod tests {
         many.sort();
         assert_eq!(many, [&10, &69, &420]);
     }
+
+    #[test]
+    fn test_remove_many() {
+        let mut grid = Grid::new(10, 10, 1.);
+
+        assert_eq!(grid.set_many(7, Row(1)..=Row(2), Col(1)..=Col(2)), Some(()));
+        assert_eq!(grid.set_unique(3, &Row(2), &Col(2)), Some(true));
+        grid.remove_many(&7, Row(1)..=Row(2), Col(1)..=Col(2));
+
+        assert_eq!(grid.get(&Row(1), &Col(1)), Some(&vec![]));
+        assert_eq!(grid.get(&Row(2), &Col(2)), Some(&vec![3]));
+    }
+
+    #[test]
+    fn test_clear() {
+        let mut grid = Grid::new(10, 10, 1.);
+        (0..20).for_each(|item| {
+            grid.set_many(item, Row(1)..=Row(2), Col(1)..=Col(2));
+        });
+        grid.clear();
+
+        assert_eq!(grid.get(&Row(1), &Col(1)), Some(&vec![]));
+        assert!(grid.get(&Row(2), &Col(2)).unwrap().capacity() >= 20);
+    }
 }
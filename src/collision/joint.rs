@@ -0,0 +1,189 @@
+use raylib::math::Vector2;
+
+use crate::{arena::Handle, entity::block::Block, traits::Sides};
+
+// Bodies are joined at their centers
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum JointAnchor {
+    Body(Handle),
+    // Fixed point in the world
+    Point(Vector2),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum JointKind {
+    Distance {
+        length: f32,
+    },
+    // Only stops the anchors from getting further apart than `length`
+    Rope {
+        length: f32,
+    },
+    // Force per pixel of stretch and per pixel per second of relative speed
+    Spring {
+        length: f32,
+        stiffness: f32,
+        damping: f32,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Joint {
+    pub body: Handle,
+    pub anchor: JointAnchor,
+    pub kind: JointKind,
+}
+
+impl Joint {
+    #[must_use]
+    pub fn new(body: Handle, anchor: JointAnchor, kind: JointKind) -> Self {
+        Self { body, anchor, kind }
+    }
+
+    // Keeps the center of the body on `point`
+    #[must_use]
+    pub fn pin(body: Handle, point: Vector2) -> Self {
+        Self::new(
+            body,
+            JointAnchor::Point(point),
+            JointKind::Distance { length: 0. },
+        )
+    }
+
+    // The other body, `None` when joined to a point
+    #[must_use]
+    pub fn other(&self) -> Option<Handle> {
+        match self.anchor {
+            JointAnchor::Body(other) => Some(other),
+            JointAnchor::Point(_) => None,
+        }
+    }
+
+    // Distance and rope joints correct the speeds so the positions after the update keep the length.
+    // Called once per solver iteration, like contacts.
+    pub fn apply(&self, block: &mut Block, other: Option<&mut Block>) {
+        let (length, rope) = match self.kind {
+            JointKind::Distance { length } => (length, false),
+            JointKind::Rope { length } => (length, true),
+            JointKind::Spring { .. } => return,
+        };
+
+        let other_inverse_mass = other.as_ref().map_or(0., |other| other.inverse_mass());
+        let inverse_mass_sum = block.inverse_mass() + other_inverse_mass;
+        if inverse_mass_sum <= 0. {
+            return;
+        }
+
+        let other_speed = other
+            .as_ref()
+            .map_or(Vector2::zero(), |other| other.speed());
+        let end = self.anchor_position(other.as_deref()) + other_speed;
+        let delta = end - (block.center() + block.speed());
+        let distance = delta.length();
+        let error = distance - length;
+        if distance <= f32::EPSILON || (rope && error <= 0.) {
+            return;
+        }
+
+        let impulse = delta / distance * (error / inverse_mass_sum);
+        block.set_speed(block.speed() + impulse * block.inverse_mass());
+        if let Some(other) = other {
+            other.set_speed(other.speed() - impulse * other_inverse_mass);
+        }
+    }
+
+    // Force pulling the block towards the anchor, the other body gets the opposite one
+    pub fn spring_force(&self, block: &Block, other: Option<&Block>, delta: f32) -> Vector2 {
+        let JointKind::Spring {
+            length,
+            stiffness,
+            damping,
+        } = self.kind
+        else {
+            return Vector2::zero();
+        };
+
+        let offset = self.anchor_position(other) - block.center();
+        let distance = offset.length();
+        if distance <= f32::EPSILON || delta <= 0. {
+            return Vector2::zero();
+        }
+
+        let normal = offset / distance;
+        let relative_speed = (other.map_or(Vector2::zero(), Block::speed) - block.speed()) / delta;
+        normal * (stiffness * (distance - length) + damping * relative_speed.dot(normal))
+    }
+
+    pub fn anchor_position(&self, other: Option<&Block>) -> Vector2 {
+        match self.anchor {
+            JointAnchor::Point(point) => point,
+            JointAnchor::Body(_) => other.map_or(Vector2::zero(), Sides::center),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use raylib::math::Rectangle;
+
+    use crate::arena::Arena;
+
+    use super::*;
+
+    fn handles() -> (Handle, Handle) {
+        let mut arena = Arena::new();
+        (arena.insert(()), arena.insert(()))
+    }
+
+    #[test]
+    fn test_rope_is_slack_when_short() {
+        let mut block = Block::new(Rectangle::new(0., 0., 10., 10.), Vector2::zero());
+        block.set_speed(Vector2::new(2., 0.));
+        let rope = Joint::new(
+            handles().0,
+            JointAnchor::Point(Vector2::new(5., 25.)),
+            JointKind::Rope { length: 30. },
+        );
+
+        rope.apply(&mut block, None);
+        assert_eq!(block.speed(), Vector2::new(2., 0.));
+
+        let distance = Joint::new(rope.body, rope.anchor, JointKind::Distance { length: 30. });
+        distance.apply(&mut block, None);
+        let center = block.center() + block.speed();
+        assert!((center.distance_to(Vector2::new(5., 25.)) - 30.).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_distance_between_bodies() {
+        let mut light = Block::new(Rectangle::new(0., 0., 10., 10.), Vector2::zero());
+        let mut heavy = Block::new(Rectangle::new(50., 0., 10., 10.), Vector2::zero());
+        light.set_mass(1.);
+        heavy.set_mass(3.);
+        let (a, b) = handles();
+        let joint = Joint::new(a, JointAnchor::Body(b), JointKind::Distance { length: 30. });
+
+        joint.apply(&mut light, Some(&mut heavy));
+        assert!((light.speed().x - 15.).abs() < 1e-4);
+        assert!((heavy.speed().x + 5.).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_spring_force() {
+        let block = Block::new(Rectangle::new(0., 0., 10., 10.), Vector2::zero());
+        let spring = Joint::new(
+            handles().0,
+            JointAnchor::Point(Vector2::new(25., 5.)),
+            JointKind::Spring {
+                length: 10.,
+                stiffness: 2.,
+                damping: 0.,
+            },
+        );
+
+        assert_eq!(
+            spring.spring_force(&block, None, 0.1),
+            Vector2::new(20., 0.)
+        );
+    }
+}
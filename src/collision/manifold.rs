@@ -0,0 +1,111 @@
+use raylib::math::Vector2;
+
+use crate::{
+    entity::segment::Segment,
+    geometry::{Scalar, Vector},
+    traits::Sides,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Manifold<V: Vector = Vector2> {
+    // Unit vector pointing from the first shape towards the second one
+    pub normal: V,
+    pub depth: V::Scalar,
+}
+
+impl<V: Vector> Manifold<V> {
+    // Separating axis test, both shapes are convex so the axes are the normals of their sides.
+    pub fn between<A: Sides<V> + ?Sized, B: Sides<V> + ?Sized>(a: &A, b: &B) -> Option<Self> {
+        let a_sides = [a.top(), a.right(), a.bottom(), a.left()];
+        let b_sides = [b.top(), b.right(), b.bottom(), b.left()];
+
+        let mut best: Option<Self> = None;
+        for side in a_sides.iter().chain(&b_sides) {
+            let delta = side.end - side.start;
+            if delta.length_sqr() <= V::Scalar::EPSILON {
+                continue;
+            }
+
+            let axis = V::new(-delta.y(), delta.x()) / delta.length();
+            let manifold = Self::along(a, b, axis)?;
+            if best.is_none_or(|best| manifold.depth < best.depth) {
+                best = Some(manifold);
+            }
+        }
+
+        best
+    }
+
+    // Overlap of both shapes projected on a single unit axis
+    pub fn along<A: Sides<V> + ?Sized, B: Sides<V> + ?Sized>(
+        a: &A,
+        b: &B,
+        axis: V,
+    ) -> Option<Self> {
+        let project = |sides: [Segment<V>; 4]| {
+            sides.iter().flat_map(|side| [side.start, side.end]).fold(
+                (V::Scalar::MAX, V::Scalar::MIN),
+                |(min, max), corner| {
+                    let projection = corner.dot(axis);
+                    (min.min(projection), max.max(projection))
+                },
+            )
+        };
+
+        let (a_min, a_max) = project([a.top(), a.right(), a.bottom(), a.left()]);
+        let (b_min, b_max) = project([b.top(), b.right(), b.bottom(), b.left()]);
+        let depth = a_max.min(b_max) - a_min.max(b_min);
+        if depth <= V::Scalar::ZERO {
+            return None;
+        }
+
+        let normal = if a_min + a_max > b_min + b_max {
+            -axis
+        } else {
+            axis
+        };
+
+        Some(Self { normal, depth })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use raylib::math::Rectangle;
+
+    use crate::entity::wall::Wall;
+
+    use super::*;
+
+    #[test]
+    fn test_separated() {
+        let a = Rectangle::new(0., 0., 10., 10.);
+        let b = Rectangle::new(20., 0., 10., 10.);
+
+        assert_eq!(Manifold::between(&a, &b), None);
+    }
+
+    #[test]
+    fn test_overlap_picks_shallowest_axis() {
+        let a = Rectangle::new(0., 0., 10., 10.);
+        let b = Rectangle::new(8., 3., 10., 10.);
+
+        let manifold = Manifold::between(&a, &b).unwrap();
+        assert_eq!(manifold.normal, Vector2::new(1., 0.));
+        assert!((manifold.depth - 2.).abs() < f32::EPSILON);
+
+        let manifold = Manifold::between(&b, &a).unwrap();
+        assert_eq!(manifold.normal, Vector2::new(-1., 0.));
+    }
+
+    #[test]
+    fn test_rotated_wall() {
+        let wall = Wall::new(Vector2::new(0., 0.), Vector2::new(100., 100.));
+        let block = Rectangle::new(48., 46., 4., 4.);
+
+        let manifold = Manifold::between(&block, &wall).unwrap();
+        let diagonal = std::f32::consts::FRAC_1_SQRT_2;
+        assert!((manifold.normal.x + diagonal).abs() < 1e-4);
+        assert!((manifold.normal.y - diagonal).abs() < 1e-4);
+    }
+}
@@ -1,3 +1,8 @@
+pub mod broad_phase;
 pub(crate) mod collision_result;
-#[allow(clippy::must_use_candidate)]
+pub mod contact;
+pub mod filter;
 pub mod grid;
+pub mod joint;
+pub mod manifold;
+pub mod solver;
@@ -0,0 +1,317 @@
+use raylib::math::Vector2;
+
+use crate::{
+    arena::{Arena, Handle},
+    entity::block::Block,
+    geometry::{Scalar, Vector},
+    traits::RigidBody,
+};
+
+use super::{
+    contact::{Contact, Friction},
+    manifold::Manifold,
+};
+
+// Speeds are displacements per step, so the impulses are too.
+// Without `other` the block touches something with infinite mass, like a wall, moving at `other_speed`.
+// The constraint copies what it needs from the contact, worlds keep them between steps.
+#[derive(Debug, Clone, Copy)]
+pub struct ContactConstraint<V: Vector = Vector2> {
+    normal: V,
+    other_speed: V,
+    friction: Friction<V::Scalar>,
+    target_speed: V::Scalar,
+    normal_impulse: V::Scalar,
+    tangent_impulse: V::Scalar,
+}
+
+impl ContactConstraint {
+    // Below `resting_speed` the bodies are considered resting on each other and don't bounce
+    #[must_use]
+    pub fn new(
+        contact: &Contact,
+        block: &Block,
+        other: Option<&Block>,
+        resting_speed: f32,
+    ) -> Self {
+        Self::along(
+            contact.manifold.normal,
+            contact.other_speed,
+            contact.restitution,
+            block,
+            other,
+            resting_speed,
+        )
+        .with_friction(contact.friction)
+    }
+}
+
+impl<V: Vector> ContactConstraint<V> {
+    // Same as `new` for any body, without friction until `with_friction`
+    #[must_use]
+    pub fn along<B: RigidBody<V>>(
+        normal: V,
+        other_speed: V,
+        restitution: V::Scalar,
+        block: &B,
+        other: Option<&B>,
+        resting_speed: V::Scalar,
+    ) -> Self {
+        let normal_speed = relative_speed(other_speed, block, other).dot(normal);
+        let target_speed = if normal_speed < -resting_speed {
+            -restitution * normal_speed
+        } else {
+            V::Scalar::ZERO
+        };
+
+        Self {
+            normal,
+            other_speed,
+            friction: Friction::default(),
+            target_speed,
+            normal_impulse: V::Scalar::ZERO,
+            tangent_impulse: V::Scalar::ZERO,
+        }
+    }
+
+    #[must_use]
+    pub fn with_friction(self, friction: Friction<V::Scalar>) -> Self {
+        Self { friction, ..self }
+    }
+
+    // Called once per solver iteration, the accumulated impulses are clamped instead of each one
+    pub fn apply<B: RigidBody<V>>(&mut self, block: &mut B, mut other: Option<&mut B>) {
+        let zero = V::Scalar::ZERO;
+        let other_inverse_mass = other.as_ref().map_or(zero, |other| other.inverse_mass());
+        let inverse_mass_sum = block.inverse_mass() + other_inverse_mass;
+        if inverse_mass_sum <= zero {
+            return;
+        }
+
+        let normal = self.normal;
+        let normal_speed = relative_speed(self.other_speed, block, other.as_deref()).dot(normal);
+        let accumulated =
+            (self.normal_impulse + (self.target_speed - normal_speed) / inverse_mass_sum).max(zero);
+        let impulse = normal * (accumulated - self.normal_impulse);
+        self.normal_impulse = accumulated;
+        apply_impulse(block, other.as_deref_mut(), impulse, other_inverse_mass);
+
+        // Coulomb friction: sticks while under the static limit, slides with the dynamic one otherwise
+        let tangent = V::new(-normal.y(), normal.x());
+        let tangent_speed = relative_speed(self.other_speed, block, other.as_deref()).dot(tangent);
+        let friction = self.friction;
+        let mut accumulated = self.tangent_impulse - tangent_speed / inverse_mass_sum;
+        if accumulated.abs() > friction.static_coefficient * self.normal_impulse {
+            let limit = friction.dynamic_coefficient * self.normal_impulse;
+            accumulated = accumulated.clamp(-limit, limit);
+        }
+        let impulse = tangent * (accumulated - self.tangent_impulse);
+        self.tangent_impulse = accumulated;
+        apply_impulse(block, other, impulse, other_inverse_mass);
+    }
+}
+
+fn relative_speed<V: Vector, B: RigidBody<V>>(other_speed: V, block: &B, other: Option<&B>) -> V {
+    other.map_or(other_speed, RigidBody::speed) - block.speed()
+}
+
+fn apply_impulse<V: Vector, B: RigidBody<V>>(
+    block: &mut B,
+    other: Option<&mut B>,
+    impulse: V,
+    other_inverse_mass: V::Scalar,
+) {
+    block.set_speed(block.speed() - impulse * block.inverse_mass());
+    if let Some(other) = other {
+        other.set_speed(other.speed() + impulse * other_inverse_mass);
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct PositionCorrection<S = f32> {
+    // Penetration allowed before correcting, keeps resting contacts touching
+    pub slop: S,
+    // Fraction of the remaining penetration removed on each step
+    pub factor: S,
+}
+
+impl<S: Scalar> Default for PositionCorrection<S> {
+    fn default() -> Self {
+        Self {
+            slop: S::from_f32(0.1),
+            factor: S::from_f32(0.4),
+        }
+    }
+}
+
+impl<S: Scalar> PositionCorrection<S> {
+    // Moves the bodies apart along the normal without changing their speed
+    pub fn apply<V, B>(&self, block: &mut B, other: Option<&mut B>, manifold: &Manifold<V>)
+    where
+        V: Vector<Scalar = S>,
+        B: RigidBody<V>,
+    {
+        let other_inverse_mass = other.as_ref().map_or(S::ZERO, |other| other.inverse_mass());
+        let inverse_mass_sum = block.inverse_mass() + other_inverse_mass;
+        let depth = manifold.depth - self.slop;
+        if depth <= S::ZERO || inverse_mass_sum <= S::ZERO {
+            return;
+        }
+
+        let correction = manifold.normal * (depth * self.factor / inverse_mass_sum);
+        block.translate(-correction * block.inverse_mass());
+        if let Some(other) = other {
+            other.translate(correction * other_inverse_mass);
+        }
+    }
+}
+
+// Runs `f` with the block of `handle` and the one of `other`, if any.
+// Nothing is run when any of them is missing.
+pub(crate) fn with_pair<F>(blocks: &mut Arena<Block>, handle: Handle, other: Option<Handle>, f: F)
+where
+    F: FnOnce(&mut Block, Option<&mut Block>),
+{
+    match other {
+        Some(other) => {
+            if let Some((block, other)) = blocks.get_pair_mut(handle, other) {
+                f(block, Some(other));
+            }
+        }
+        None => {
+            if let Some(block) = blocks.get_mut(handle) {
+                f(block, None);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use raylib::math::Rectangle;
+
+    use crate::{
+        collision::{collision_result::CollisionResult, manifold::Manifold},
+        traits::Position,
+    };
+
+    use super::*;
+
+    fn head_on(restitution: f32) -> (Block, Block) {
+        let mut light = Block::new(Rectangle::new(0., 0., 10., 10.), Vector2::zero());
+        let mut heavy = Block::new(Rectangle::new(9., 0., 10., 10.), Vector2::zero());
+        light.set_mass(1.);
+        heavy.set_mass(3.);
+        light.set_speed(Vector2::new(6., 0.));
+        heavy.set_speed(Vector2::new(-2., 0.));
+
+        let manifold = Manifold::between(&light, &heavy).unwrap();
+        let mut contact = Contact::new(
+            Some(Arena::new().insert(())),
+            CollisionResult::default(),
+            manifold,
+        );
+        contact.restitution = restitution;
+
+        let mut constraint = ContactConstraint::new(&contact, &light, Some(&heavy), 0.);
+        constraint.apply(&mut light, Some(&mut heavy));
+        (light, heavy)
+    }
+
+    fn slide(speed: Vector2, friction: Friction) -> Vector2 {
+        let mut block = Block::new(Rectangle::new(0., 0., 10., 10.), Vector2::zero());
+        let floor = Rectangle::new(-50., 9., 100., 10.);
+        block.set_speed(speed);
+
+        let manifold = Manifold::between(&block, &floor).unwrap();
+        let mut contact = Contact::new(None, CollisionResult::default(), manifold);
+        contact.restitution = 0.;
+        contact.friction = friction;
+
+        let mut constraint = ContactConstraint::new(&contact, &block, None, 0.);
+        (0..4).for_each(|_| constraint.apply(&mut block, None));
+        block.speed()
+    }
+
+    #[test]
+    fn test_elastic_head_on() {
+        let (light, heavy) = head_on(1.);
+
+        assert_eq!(light.speed(), Vector2::new(-6., 0.));
+        assert_eq!(heavy.speed(), Vector2::new(2., 0.));
+    }
+
+    #[test]
+    fn test_inelastic_head_on() {
+        let (light, heavy) = head_on(0.);
+
+        assert_eq!(light.speed(), Vector2::zero());
+        assert_eq!(heavy.speed(), Vector2::zero());
+    }
+
+    #[test]
+    fn test_static_other() {
+        let mut block = Block::new(Rectangle::new(0., 0., 10., 10.), Vector2::zero());
+        let wall = Rectangle::new(9., -10., 10., 30.);
+        block.set_speed(Vector2::new(4., 3.));
+
+        let manifold = Manifold::between(&block, &wall).unwrap();
+        let mut contact = Contact::new(None, CollisionResult::default(), manifold);
+        contact.restitution = 0.5;
+
+        let mut constraint = ContactConstraint::new(&contact, &block, None, 0.);
+        constraint.apply(&mut block, None);
+        assert_eq!(block.speed(), Vector2::new(-2., 3.));
+    }
+
+    #[test]
+    fn test_resting_contact_does_not_bounce() {
+        let mut block = Block::new(Rectangle::new(0., 0., 10., 10.), Vector2::zero());
+        let floor = Rectangle::new(-50., 9., 100., 10.);
+        block.set_speed(Vector2::new(0., 0.2));
+
+        let manifold = Manifold::between(&block, &floor).unwrap();
+        let contact = Contact::new(None, CollisionResult::default(), manifold);
+
+        let mut constraint = ContactConstraint::new(&contact, &block, None, 0.5);
+        constraint.apply(&mut block, None);
+        assert!(block.speed().length() < 1e-5);
+    }
+
+    #[test]
+    fn test_friction_sticks_and_slides() {
+        let frictionless = slide(Vector2::new(2., 4.), Friction::default());
+        assert_eq!(frictionless, Vector2::new(2., 0.));
+
+        let sticky = slide(Vector2::new(2., 4.), Friction::new(0.6, 0.4));
+        assert_eq!(sticky, Vector2::zero());
+
+        let sliding = slide(Vector2::new(2., 4.), Friction::new(0.2, 0.1));
+        assert!((sliding.x - 1.6).abs() < 1e-5);
+        assert!(sliding.y.abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_position_correction_keeps_speed() {
+        let mut block = Block::new(Rectangle::new(0., 0., 10., 10.), Vector2::zero());
+        let mut other = Block::new(Rectangle::new(8., 0., 10., 10.), Vector2::zero());
+        block.set_speed(Vector2::new(1., 0.));
+
+        let manifold = Manifold::between(&block, &other).unwrap();
+        let contact = Contact::new(
+            Some(Arena::new().insert(())),
+            CollisionResult::default(),
+            manifold,
+        );
+        let correction = PositionCorrection {
+            slop: 0.5,
+            factor: 0.5,
+        };
+
+        correction.apply(&mut block, Some(&mut other), &contact.manifold);
+        assert_eq!(block.position(), Vector2::new(-0.375, 0.));
+        assert_eq!(other.position(), Vector2::new(8.375, 0.));
+        assert_eq!(block.speed(), Vector2::new(1., 0.));
+        assert_eq!(other.speed(), Vector2::zero());
+    }
+}
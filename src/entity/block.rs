@@ -6,46 +6,129 @@ use raylib::{
 };
 
 use crate::{
+    arena::Handle,
     collision::{
+        broad_phase::{Bodies, GridKey},
         collision_result::CollisionResult,
-        grid::{Col, Grid, Row},
+        contact::{Contact, Friction},
+        filter::CollisionFilter,
+        grid::Grid,
+        manifold::Manifold,
     },
-    traits::{Collision, Draw, GridItemTrait, Position, Redirect, Sides},
+    geometry::Verlet,
+    traits::{Collision, ContactHook, Draw, GridItemTrait, Position, RigidBody, Sides},
 };
 
-use super::segment::Segment;
+use super::{body::BodyKind, segment::Segment};
+
+// Work done by `Block::contacts_with`, for the step metrics
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct NarrowPhaseCounts {
+    // Candidates that went through the narrow phase
+    pub tested_pairs: usize,
+    // `check_collision` calls, one for every candidate touching the block
+    pub collision_checks: usize,
+}
 
 #[derive(Debug)]
 pub struct Block {
-    id: usize,
+    handle: Option<Handle>,
     rec: Rectangle,
     old_rec: Rectangle,
     color: Color,
     acc: Vector2,
+    filter: CollisionFilter,
+    mass: f32,
+    restitution: f32,
+    friction: Friction,
+    damping: f32,
+    kind: BodyKind,
+    // Child rectangles relative to the position, empty when the block is a single one
+    shapes: Vec<Rectangle>,
+    sleeping: bool,
+    // Seconds spent below the sleep speed
+    sleep_time: f32,
+}
+
+// `clone_from` keeps the child shapes allocation
+impl Clone for Block {
+    fn clone(&self) -> Self {
+        Self {
+            shapes: self.shapes.clone(),
+            ..*self
+        }
+    }
+
+    fn clone_from(&mut self, source: &Self) {
+        let mut shapes = std::mem::take(&mut self.shapes);
+        shapes.clone_from(&source.shapes);
+        *self = Self { shapes, ..*source };
+    }
 }
 
 impl Block {
+    #[must_use]
     pub fn new(rec: Rectangle, acc: Vector2) -> Self {
         Self {
-            id: usize::default(),
+            handle: None,
             rec,
             old_rec: rec,
             acc,
             color: Color::BLACK,
+            filter: CollisionFilter::default(),
+            mass: rec.width * rec.height,
+            restitution: 1.,
+            friction: Friction::default(),
+            damping: 0.,
+            kind: BodyKind::Dynamic,
+            shapes: vec![],
+            sleeping: false,
+            sleep_time: 0.,
         }
     }
 
-    pub fn new_random<F>(
-        id: usize,
-        size: i32,
-        max_width: i32,
-        max_height: i32,
-        mut get_random: F,
-    ) -> Self
+    // A single rigid body made of several rectangles, given in world coordinates.
+    // The block rectangle becomes the bounding box of all of them, without any there is no block.
+    #[must_use]
+    pub fn compound(shapes: &[Rectangle], acc: Vector2) -> Option<Self> {
+        let first = shapes.first()?;
+        let min = shapes.iter().fold(first.position(), |acc, shape| {
+            Vector2::new(acc.x.min(shape.x), acc.y.min(shape.y))
+        });
+        let max = shapes.iter().fold(
+            first.position() + Vector2::new(first.width, first.height),
+            |acc, shape| {
+                Vector2::new(
+                    acc.x.max(shape.x + shape.width),
+                    acc.y.max(shape.y + shape.height),
+                )
+            },
+        );
+
+        let mut block = Self::new(
+            Rectangle::new(min.x, min.y, max.x - min.x, max.y - min.y),
+            acc,
+        );
+        block.shapes = shapes
+            .iter()
+            .map(|shape| Rectangle {
+                x: shape.x - min.x,
+                y: shape.y - min.y,
+                ..*shape
+            })
+            .collect();
+        block.mass = block.area();
+        Some(block)
+    }
+
+    // Distance `new_random` keeps between the blocks and the borders
+    pub const PADDING: i32 = 20;
+
+    pub fn new_random<F>(size: i32, max_width: i32, max_height: i32, mut get_random: F) -> Self
     where
         F: FnMut(i32, i32) -> f32,
     {
-        let padding = 20;
+        let padding = Self::PADDING;
         let width = size;
         let height = size;
 
@@ -59,77 +142,333 @@ impl Block {
         let direction = Vector2::new(get_random(-1, 1).signum(), get_random(-1, 1).signum());
 
         Self {
-            id,
+            handle: None,
             rec,
             old_rec: rec,
             color: Color::color_from_hsv(get_random(0, 360), 0.9, 0.9),
             acc: Vector2::new(get_random(5000, 10000), get_random(5000, 10000)) * direction,
+            filter: CollisionFilter::default(),
+            mass: rec.width * rec.height,
+            restitution: 1.,
+            friction: Friction::default(),
+            damping: 0.,
+            kind: BodyKind::Dynamic,
+            shapes: vec![],
+            sleeping: false,
+            sleep_time: 0.,
         }
     }
 
+    // Given by the world when the block is added to it
+    #[must_use]
+    pub fn handle(&self) -> Option<Handle> {
+        self.handle
+    }
+
+    pub(crate) fn set_handle(&mut self, handle: Handle) {
+        self.handle = Some(handle);
+    }
+
+    pub fn set_collision_filter(&mut self, filter: CollisionFilter) {
+        self.filter = filter;
+    }
+
     pub fn draw_debug(&self, canvas: &mut RaylibDrawHandle) {
-        let segments = [self.top(), self.right(), self.bottom(), self.left()];
+        let segments = self
+            .shapes()
+            .flat_map(|shape| [shape.top(), shape.right(), shape.bottom(), shape.left()])
+            .collect::<Vec<_>>();
+        let (line, corner) = if self.sleeping {
+            (Color::GRAY, Color::BLUE)
+        } else {
+            (Color::BLACK, Color::RED)
+        };
 
         for Segment { start, end } in &segments {
-            canvas.draw_line_v(start, end, Color::BLACK);
-            canvas.draw_circle_v(start, 5., Color::RED);
-            canvas.draw_circle_v(end, 5., Color::RED);
-        }
-    }
-
-    pub fn update(&mut self, delta: f32, collided: &Option<CollisionResult>) {
-        let initial_speed = self.position() - self.old_rec.position();
-
-        let speed = if let Some(collision) = collided {
-            #[allow(clippy::match_same_arms)]
-            match collision.0 {
-                //Top, Right, Bottom, Left
-
-                // No collision
-                [None, None, None, None] => initial_speed,
-                // One edge only
-                [Some(_), None, None, None] => initial_speed.move_down(),
-                [None, Some(_), None, None] => initial_speed.move_left(),
-                [None, None, Some(_), None] => initial_speed.move_up(),
-                [None, None, None, Some(_)] => initial_speed.move_right(),
-                // Simple 2 edges
-                [Some(_), Some(_), None, None] => initial_speed.move_down().move_left(),
-                [Some(_), None, None, Some(_)] => initial_speed.move_down().move_right(),
-                [None, Some(_), Some(_), None] => initial_speed.move_left().move_up(),
-                [None, None, Some(_), Some(_)] => initial_speed.move_up().move_right(),
-                // Hard 2 edges
-                // TODO: use normal vector to handle this case
-                [None, Some(_), None, Some(_)] => Vector2::new(initial_speed.x, -initial_speed.y),
-                [Some(_), None, Some(_), None] => Vector2::new(-initial_speed.x, initial_speed.y),
-                // Undefined behavior
-                [None, Some(_), Some(_), Some(_)] => initial_speed,
-                [Some(_), None, Some(_), Some(_)] => initial_speed,
-                [Some(_), Some(_), None, Some(_)] => initial_speed,
-                [Some(_), Some(_), Some(_), None] => initial_speed,
-                [Some(_), Some(_), Some(_), Some(_)] => initial_speed,
-            }
+            canvas.draw_line_v(start, end, line);
+            canvas.draw_circle_v(start, 5., corner);
+            canvas.draw_circle_v(end, 5., corner);
+        }
+    }
+
+    // World coordinates of the rectangles the block is made of
+    pub fn shapes(&self) -> impl Iterator<Item = Rectangle> + Clone + '_ {
+        let (shapes, offset) = if self.shapes.is_empty() {
+            (std::slice::from_ref(&self.rec), Vector2::zero())
         } else {
-            initial_speed
+            (self.shapes.as_slice(), self.rec.position())
         };
 
-        self.old_rec = self.rec;
+        shapes.iter().map(move |shape| Rectangle {
+            x: shape.x + offset.x,
+            y: shape.y + offset.y,
+            ..*shape
+        })
+    }
 
-        let new_position = self.position() + speed + self.acc * delta * delta;
-        self.rec.set_position(new_position);
+    #[must_use]
+    pub fn rec(&self) -> Rectangle {
+        self.rec
+    }
+
+    #[must_use]
+    pub fn color(&self) -> Color {
+        self.color
+    }
+
+    pub fn set_color(&mut self, color: Color) {
+        self.color = color;
+    }
+
+    // Forces applied since the last step, divided by the mass
+    #[must_use]
+    pub fn acc(&self) -> Vector2 {
+        self.acc
+    }
+
+    // Where the block was before the last update
+    #[must_use]
+    pub fn old_rec(&self) -> Rectangle {
+        self.old_rec
+    }
+
+    #[must_use]
+    pub fn speed(&self) -> Vector2 {
+        self.position() - self.old_rec.position()
+    }
+
+    pub fn set_speed(&mut self, speed: Vector2) {
+        self.old_rec.set_position(self.position() - speed);
+    }
+
+    // Moves the block keeping its speed
+    pub fn translate(&mut self, offset: Vector2) {
+        self.rec.set_position(self.position() + offset);
+        self.old_rec.set_position(self.old_rec.position() + offset);
+    }
+
+    #[must_use]
+    pub fn mass(&self) -> f32 {
+        self.mass
+    }
+
+    // Static, kinematic and sleeping blocks behave as if their mass was infinite
+    #[must_use]
+    pub fn inverse_mass(&self) -> f32 {
+        if self.kind.is_dynamic() && !self.sleeping {
+            1. / self.mass
+        } else {
+            0.
+        }
+    }
+
+    pub fn set_body_kind(&mut self, kind: BodyKind) {
+        self.kind = kind;
+        self.wake();
+        if kind == BodyKind::Static {
+            self.old_rec = self.rec;
+        }
+    }
+
+    pub fn set_mass(&mut self, mass: f32) {
+        self.mass = mass;
+    }
+
+    pub fn set_density(&mut self, density: f32) {
+        self.mass = self.area() * density;
+    }
+
+    pub fn set_restitution(&mut self, restitution: f32) {
+        self.restitution = restitution;
+    }
+
+    pub fn set_friction(&mut self, friction: Friction) {
+        self.friction = friction;
+    }
+
+    pub fn set_damping(&mut self, damping: f32) {
+        self.damping = damping;
+    }
+
+    pub fn apply_force(&mut self, force: Vector2) {
+        if self.sleeping && force != Vector2::zero() {
+            self.wake();
+        }
+        self.acc += force * self.inverse_mass();
+    }
+
+    // Same units as the solver: mass times displacement per step
+    pub fn apply_impulse(&mut self, impulse: Vector2) {
+        if self.sleeping && impulse != Vector2::zero() {
+            self.wake();
+        }
+        self.set_speed(self.speed() + impulse * self.inverse_mass());
+    }
+
+    #[must_use]
+    pub fn is_sleeping(&self) -> bool {
+        self.sleeping
+    }
+
+    pub fn wake(&mut self) {
+        self.sleeping = false;
+        self.sleep_time = 0.;
+    }
+
+    // Dynamic blocks slower than `sleep_speed` for `time_to_sleep` seconds stop moving
+    pub fn update_sleep(&mut self, delta: f32, sleep_speed: f32, time_to_sleep: f32) {
+        if !self.kind.is_dynamic() || self.sleeping {
+            return;
+        }
+
+        if self.speed().length() > sleep_speed {
+            self.sleep_time = 0.;
+            return;
+        }
+
+        self.sleep_time += delta;
+        if self.sleep_time >= time_to_sleep {
+            self.sleeping = true;
+            self.old_rec = self.rec;
+        }
+    }
+
+    // Forces are accumulated between steps and cleared once they reach the speed
+    pub fn integrate_forces(&mut self, delta: f32) {
+        if self.kind.is_dynamic() && !self.sleeping {
+            let mut motion = self.motion();
+            motion.integrate(self.acc, delta, self.damping);
+            self.set_speed(motion.speed());
+        }
         self.acc = Vector2::zero();
     }
 
-    pub fn calculate_collisions<'a>(
-        &'a self,
-        grid: &'a Grid<&'a dyn GridItemTrait<'a>>,
-    ) -> Option<CollisionResult> {
-        let (rows, cols) = self.calculate_grid_ranges(grid.spacing);
-        let cols = Col(cols.start().0.saturating_sub(1))..=Col(cols.end().0.saturating_add(1));
-        let rows = Row(rows.start().0.saturating_sub(1))..=Row(rows.end().0.saturating_add(1));
-        grid.get_many(rows, cols)
-            .iter()
-            .filter(|item| ****item != self)
-            .find_map(|item| self.check_collision(**item).into_option())
+    pub fn update(&mut self) {
+        if self.kind == BodyKind::Static || self.sleeping {
+            return;
+        }
+
+        let mut motion = self.motion();
+        motion.update();
+        self.old_rec = self.rec;
+        self.rec.set_position(motion.position);
+    }
+
+    fn motion(&self) -> Verlet<Vector2> {
+        Verlet {
+            position: self.position(),
+            old_position: self.old_rec.position(),
+        }
+    }
+
+    // Broad phase of the block contacts, `keys` is replaced by the bodies near the block. Sleeping
+    // blocks don't look for contacts.
+    pub fn contact_candidates(
+        &self,
+        grid: &Grid<GridKey>,
+        bodies: Bodies,
+        keys: &mut Vec<GridKey>,
+    ) {
+        keys.clear();
+        if !self.sleeping {
+            self.get_candidates(grid, bodies, keys);
+        }
+    }
+
+    // Narrow phase of the block contacts, the manifolds of every candidate that `needs_check`
+    // accepts are computed. They also find bodies fully inside each other, where no edges cross,
+    // `check_collision` only fills the contact result. The contacts found are pushed to `contacts`.
+    pub fn contacts_with<'a>(
+        &self,
+        candidates: impl IntoIterator<Item = (GridKey, &'a dyn GridItemTrait)>,
+        hook: Option<&dyn ContactHook>,
+        contacts: &mut Vec<Contact>,
+    ) -> NarrowPhaseCounts {
+        let speed = self.speed();
+        let mut counts = NarrowPhaseCounts::default();
+
+        for (key, item) in candidates
+            .into_iter()
+            .filter(|&(_, item)| self.needs_check(item))
+        {
+            counts.tested_pairs += 1;
+            let mut result = None;
+
+            // One contact for every pair of child shapes touching
+            for (shape, rec) in self.shapes().enumerate() {
+                item.child_manifolds(&rec, speed, &mut |other_shape, manifold| {
+                    let result: &CollisionResult = result.get_or_insert_with(|| {
+                        counts.collision_checks += 1;
+                        self.check_collision(item)
+                    });
+                    let point = result.center().unwrap_or_else(|| self.center());
+
+                    let mut contact = Contact::new(item.handle(), result.clone(), manifold);
+                    contact.key = Some(key);
+                    contact.shape = shape;
+                    contact.other_shape = other_shape;
+                    contact.other_speed = item.speed_at(point);
+                    contact.restitution = self.restitution.min(item.restitution());
+                    contact.friction = self.friction.combine(item.friction());
+                    let accepted =
+                        hook.is_none_or(|hook| hook.on_contact(self, item, &mut contact));
+
+                    if accepted && !contact.is_passing_through(speed) {
+                        contacts.push(contact);
+                    }
+                });
+            }
+        }
+
+        counts
+    }
+
+    // Bodies that can't move don't push each other
+    pub fn needs_check(&self, item: &dyn GridItemTrait) -> bool {
+        self.kind.is_dynamic() || item.body_kind().is_dynamic()
+    }
+
+    fn area(&self) -> f32 {
+        self.shapes().map(|shape| shape.width * shape.height).sum()
+    }
+
+    // Items spanning several cells are added once. They are sorted, the order the grid was filled
+    // in doesn't change the contacts order.
+    fn get_candidates(&self, grid: &Grid<GridKey>, bodies: Bodies, keys: &mut Vec<GridKey>) {
+        let (rows, cols) = self.calculate_neighbourhood(grid.spacing);
+        keys.extend(grid.iter_many(rows, cols));
+        keys.sort_unstable();
+        keys.dedup();
+        // Blocks outside a world have no handle, only their address tells them apart
+        let is_self = |key, item: &dyn GridItemTrait| match self.handle {
+            Some(handle) => key == GridKey::Block(handle),
+            None => std::ptr::addr_eq(item, self),
+        };
+        keys.retain(|&key| {
+            bodies.get(key).is_some_and(|item| {
+                !is_self(key, item)
+                    && !item.is_sensor()
+                    && self.filter.should_collide(&item.collision_filter())
+            })
+        });
+    }
+}
+
+impl RigidBody for Block {
+    fn inverse_mass(&self) -> f32 {
+        self.inverse_mass()
+    }
+
+    fn speed(&self) -> Vector2 {
+        self.speed()
+    }
+
+    fn set_speed(&mut self, speed: Vector2) {
+        self.set_speed(speed);
+    }
+
+    fn translate(&mut self, offset: Vector2) {
+        self.translate(offset);
     }
 }
 
@@ -163,17 +502,50 @@ impl Position for Block {
 
 impl Draw for Block {
     fn draw(&self, canvas: &mut impl RaylibDraw) {
-        canvas.draw_rectangle_rec(self.rec, self.color);
+        for shape in self.shapes() {
+            canvas.draw_rectangle_rec(shape, self.color);
+        }
     }
 }
 
-impl<'a> PartialEq<&'a Block> for Block {
-    fn eq(&self, other: &&'a Block) -> bool {
-        self.id == other.id
+impl GridItemTrait for Block {
+    fn collision_filter(&self) -> CollisionFilter {
+        self.filter
     }
-}
 
-impl<'a> GridItemTrait<'a> for Block {}
+    fn handle(&self) -> Option<Handle> {
+        self.handle
+    }
+
+    fn restitution(&self) -> f32 {
+        self.restitution
+    }
+
+    fn body_kind(&self) -> BodyKind {
+        self.kind
+    }
+
+    fn speed_at(&self, _point: Vector2) -> Vector2 {
+        self.speed()
+    }
+
+    fn child_manifolds(
+        &self,
+        shape: &Rectangle,
+        _speed: Vector2,
+        found: &mut dyn FnMut(usize, Manifold),
+    ) {
+        for (index, child) in self.shapes().enumerate() {
+            if let Some(manifold) = Manifold::between(shape, &child) {
+                found(index, manifold);
+            }
+        }
+    }
+
+    fn friction(&self) -> Friction {
+        self.friction
+    }
+}
 
 impl Sides for Rectangle {
     #[inline]
@@ -215,10 +587,50 @@ impl Sides for Rectangle {
     }
 }
 
-impl<'a> PartialEq<&'a Block> for Rectangle {
-    fn eq(&self, _other: &&'a Block) -> bool {
-        false
+impl GridItemTrait for Rectangle {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compound_shapes() {
+        // L shape, the corner of the bounding box is empty
+        let block = Block::compound(
+            &[
+                Rectangle::new(10., 10., 10., 30.),
+                Rectangle::new(20., 30., 20., 10.),
+            ],
+            Vector2::zero(),
+        )
+        .unwrap();
+
+        assert_eq!(block.position(), Vector2::new(10., 10.));
+        assert_eq!(
+            block.aabb(),
+            Segment {
+                start: Vector2::new(10., 10.),
+                end: Vector2::new(40., 40.),
+            }
+        );
+        assert!((block.mass() - 500.).abs() < f32::EPSILON);
+        assert!(Block::compound(&[], Vector2::zero()).is_none());
+
+        let manifolds = |shape| {
+            let mut manifolds = vec![];
+            block.child_manifolds(&shape, Vector2::zero(), &mut |index, manifold| {
+                manifolds.push((index, manifold));
+            });
+            manifolds
+        };
+
+        let empty_corner = Rectangle::new(30., 12., 5., 5.);
+        assert!(manifolds(empty_corner).is_empty());
+
+        let on_foot = Rectangle::new(30., 27., 5., 5.);
+        let manifolds = manifolds(on_foot);
+        assert_eq!(manifolds.len(), 1);
+        assert_eq!(manifolds[0].0, 1);
+        assert_eq!(manifolds[0].1.normal, Vector2::new(0., 1.));
     }
 }
-
-impl<'a> GridItemTrait<'a> for Rectangle {}
@@ -0,0 +1,17 @@
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BodyKind {
+    // Never moves and has infinite mass
+    #[default]
+    Static,
+    // Moves with the speed it is given, pushes dynamic bodies but isn't pushed back
+    Kinematic,
+    // Moved by forces and contacts
+    Dynamic,
+}
+
+impl BodyKind {
+    #[must_use]
+    pub fn is_dynamic(self) -> bool {
+        self == Self::Dynamic
+    }
+}
@@ -1,4 +1,6 @@
-#[allow(clippy::must_use_candidate)]
 pub mod block;
+pub mod body;
 pub(crate) mod segment;
+pub mod sensor;
 pub mod wall;
+pub mod wall_chain;
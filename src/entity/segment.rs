@@ -1,16 +1,18 @@
 use raylib::{
-    check_collision_lines,
     color::Color,
     math::Vector2,
     prelude::{RaylibDraw, RaylibDrawHandle},
 };
 
-use crate::traits::Draw;
+use crate::{
+    geometry::{Scalar, Vector},
+    traits::Draw,
+};
 
-#[derive(Debug, Clone)]
-pub struct Segment {
-    pub start: Vector2,
-    pub end: Vector2,
+#[derive(Debug, Clone, PartialEq)]
+pub struct Segment<V = Vector2> {
+    pub start: V,
+    pub end: V,
 }
 
 impl Segment {
@@ -19,34 +21,77 @@ impl Segment {
         canvas.draw_circle_v(normal.start, 2., Color::FUCHSIA);
         canvas.draw_line_ex(normal.start, normal.end, 1., Color::BLACK);
     }
+}
 
+impl<V: Vector> Segment<V> {
     pub fn normal_unit(&self) -> Self {
-        self.normal_with_length(1.)
+        self.normal_with_length(V::Scalar::ONE)
     }
 
-    fn normal_with_length(&self, length: f32) -> Self {
+    fn normal_with_length(&self, length: V::Scalar) -> Self {
         let delta = self.end - self.start;
-        let scale = length / delta.length();
-        let radius = Vector2::new(-scale * delta.y, scale * delta.x);
+        // A segment without length has no direction, its normal stays a point
+        let scale = match delta.length() {
+            zero if zero == V::Scalar::ZERO => V::Scalar::ZERO,
+            delta_length => length / delta_length,
+        };
+        let radius = V::new(-scale * delta.y(), scale * delta.x());
 
-        let start = self.start + delta * 0.5;
+        let start = self.start + delta * V::Scalar::from_f32(0.5);
         let end = start - radius;
         Self { start, end }
     }
 
-    pub fn check_collision_segment(&self, other: &Self) -> Option<Vector2> {
-        check_collision_lines(self.start, self.end, other.start, other.end)
+    // Relative to the segments instead of the origin, far away coordinates don't lose precision.
+    pub fn check_collision_segment(&self, other: &Self) -> Option<V> {
+        let delta = self.end - self.start;
+        let other_delta = other.end - other.start;
+        let denominator = cross(delta, other_delta);
+        if denominator.abs() < V::Scalar::EPSILON {
+            return None;
+        }
+
+        let to_other = other.start - self.start;
+        let amount = cross(to_other, other_delta) / denominator;
+        let other_amount = cross(to_other, delta) / denominator;
+        let range = V::Scalar::ZERO..=V::Scalar::ONE;
+
+        (range.contains(&amount) && range.contains(&other_amount)).then(|| self.point_at(amount))
+    }
+
+    // Positive on one side of the line, negative on the other and zero on it.
+    pub fn side_of(&self, point: V) -> V::Scalar {
+        cross(self.end - self.start, point - self.start)
     }
 
-    pub fn angle(&self) -> f32 {
+    // Where the closest point to `point` is, from 0 at the start to 1 at the end
+    pub fn project(&self, point: V) -> V::Scalar {
+        let delta = self.end - self.start;
+        let length = delta.length_sqr();
+        if length <= V::Scalar::EPSILON {
+            return V::Scalar::ZERO;
+        }
+
+        ((point - self.start).dot(delta) / length).clamp(V::Scalar::ZERO, V::Scalar::ONE)
+    }
+
+    pub fn point_at(&self, amount: V::Scalar) -> V {
+        self.start.lerp(self.end, amount)
+    }
+
+    pub fn angle(&self) -> V::Scalar {
         self.start.angle_to(self.end)
     }
 
-    pub fn length(&self) -> f32 {
+    pub fn length(&self) -> V::Scalar {
         self.start.distance_to(self.end)
     }
 }
 
+fn cross<V: Vector>(a: V, b: V) -> V::Scalar {
+    a.x() * b.y() - a.y() * b.x()
+}
+
 impl Draw for Segment {
     fn draw(&self, canvas: &mut impl RaylibDraw) {
         canvas.draw_line_ex(self.start, self.end, 1., Color::BLACK);
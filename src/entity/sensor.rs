@@ -0,0 +1,301 @@
+use std::{fmt::Debug, sync::Arc};
+
+use raylib::{color::Color, math::Rectangle, prelude::RaylibDraw};
+
+use crate::{
+    arena::Handle,
+    collision::{
+        broad_phase::{Bodies, GridKey},
+        filter::CollisionFilter,
+        grid::Grid,
+    },
+    traits::{Collision, Draw, GridItemTrait, Sides},
+};
+
+use super::segment::Segment;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SensorEvent {
+    Enter { sensor: usize, body: Handle },
+    Leave { sensor: usize, body: Handle },
+}
+
+// Shape of the sensors stored in a world, anything with sides and `Debug`
+pub trait SensorShape: Sides + Debug {}
+
+impl<T: Sides + Debug + ?Sized> SensorShape for T {}
+
+#[derive(Debug)]
+pub struct Sensor<S = Arc<dyn SensorShape>> {
+    id: usize,
+    shape: S,
+    filter: CollisionFilter,
+    overlapping: Vec<Handle>,
+}
+
+// `clone_from` keeps the allocation of the overlapping bodies
+impl<S: Clone> Clone for Sensor<S> {
+    fn clone(&self) -> Self {
+        Self {
+            id: self.id,
+            shape: self.shape.clone(),
+            filter: self.filter,
+            overlapping: self.overlapping.clone(),
+        }
+    }
+
+    fn clone_from(&mut self, source: &Self) {
+        self.id = source.id;
+        self.shape.clone_from(&source.shape);
+        self.filter = source.filter;
+        self.overlapping.clone_from(&source.overlapping);
+    }
+}
+
+impl<S: Sides> Sensor<S> {
+    pub fn new(id: usize, shape: S) -> Self {
+        Self {
+            id,
+            shape,
+            filter: CollisionFilter::default(),
+            overlapping: vec![],
+        }
+    }
+
+    pub fn id(&self) -> usize {
+        self.id
+    }
+
+    pub fn set_collision_filter(&mut self, filter: CollisionFilter) {
+        self.filter = filter;
+    }
+
+    pub fn overlapping(&self) -> &[Handle] {
+        &self.overlapping
+    }
+
+    // Pushes the bodies overlapping the sensor to `overlaps`, sorted, `keys` is a buffer for the
+    // candidates. Once both are large enough this doesn't allocate.
+    pub fn calculate_overlaps(
+        &self,
+        grid: &Grid<GridKey>,
+        bodies: Bodies,
+        keys: &mut Vec<GridKey>,
+        overlaps: &mut Vec<Handle>,
+    ) {
+        let (rows, cols) = self.calculate_neighbourhood(grid.spacing);
+        keys.clear();
+        keys.extend(grid.iter_many(rows, cols));
+        keys.sort_unstable();
+        keys.dedup();
+
+        // Blocks come last in the keys, sorted by handle
+        overlaps.extend(
+            keys.iter()
+                .filter_map(|&key| bodies.block(key))
+                .filter(|block| self.filter.should_collide(&block.collision_filter()))
+                .filter(|block| block.shapes().any(|shape| self.overlaps(&shape)))
+                .filter_map(GridItemTrait::handle),
+        );
+    }
+
+    // `overlaps` must be sorted, the events are pushed to `events`
+    pub fn update(&mut self, overlaps: &[Handle], events: &mut Vec<SensorEvent>) {
+        let sensor = self.id;
+        let entered = overlaps
+            .iter()
+            .filter(|body| self.overlapping.binary_search(body).is_err())
+            .map(|&body| SensorEvent::Enter { sensor, body });
+        let left = self
+            .overlapping
+            .iter()
+            .filter(|body| overlaps.binary_search(body).is_err())
+            .map(|&body| SensorEvent::Leave { sensor, body });
+
+        events.extend(entered.chain(left));
+        self.overlapping.clear();
+        self.overlapping.extend_from_slice(overlaps);
+    }
+
+    // Edges crossing is not enough, the sensor can fully contain the shape or the other way around.
+    // Compound blocks are tested one child shape at a time.
+    fn overlaps(&self, other: &Rectangle) -> bool {
+        self.shape.check_collision(other).into_option().is_some()
+            || self.shape.contains_point(other.top().start)
+            || other.contains_point(self.shape.top().start)
+    }
+}
+
+impl<S: SensorShape + 'static> Sensor<S> {
+    // Worlds store every sensor with the same type, whatever its shape
+    pub fn into_shared(self) -> Sensor {
+        Sensor {
+            id: self.id,
+            shape: Arc::new(self.shape),
+            filter: self.filter,
+            overlapping: self.overlapping,
+        }
+    }
+}
+
+impl<S: Sides + ?Sized> Sides for Arc<S> {
+    fn top(&self) -> Segment {
+        (**self).top()
+    }
+
+    fn right(&self) -> Segment {
+        (**self).right()
+    }
+
+    fn bottom(&self) -> Segment {
+        (**self).bottom()
+    }
+
+    fn left(&self) -> Segment {
+        (**self).left()
+    }
+}
+
+impl<S: Sides> Sides for Sensor<S> {
+    fn top(&self) -> Segment {
+        self.shape.top()
+    }
+
+    fn right(&self) -> Segment {
+        self.shape.right()
+    }
+
+    fn bottom(&self) -> Segment {
+        self.shape.bottom()
+    }
+
+    fn left(&self) -> Segment {
+        self.shape.left()
+    }
+}
+
+impl<S: Sides> Draw for Sensor<S> {
+    fn draw(&self, canvas: &mut impl RaylibDraw) {
+        let color = if self.overlapping.is_empty() {
+            Color::LIME
+        } else {
+            Color::ORANGE
+        };
+
+        for Segment { start, end } in [self.top(), self.right(), self.bottom(), self.left()] {
+            canvas.draw_line_ex(start, end, 2., color);
+        }
+    }
+}
+
+impl<S: Sides + Debug> GridItemTrait for Sensor<S> {
+    fn collision_filter(&self) -> CollisionFilter {
+        self.filter
+    }
+
+    fn is_sensor(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use raylib::math::Vector2;
+
+    use crate::{arena::Arena, collision::broad_phase::BroadPhase, entity::block::Block};
+
+    use super::*;
+
+    fn overlaps_with(sensor: &Sensor, block: &Block) -> Vec<Handle> {
+        let blocks = Arena::from_iter([block.clone()]);
+        let bodies = Bodies {
+            blocks: &blocks,
+            walls: &[],
+            sensors: std::slice::from_ref(sensor),
+        };
+        let mut broad_phase = BroadPhase::new(10, 10, 10.);
+        broad_phase.update(bodies);
+
+        // Blocks go through sensors
+        let block = &blocks[blocks.handles().next().unwrap()];
+        let mut keys = vec![];
+        block.contact_candidates(broad_phase.grid(), bodies, &mut keys);
+        assert!(keys.is_empty());
+        let mut overlaps = vec![];
+        sensor.calculate_overlaps(broad_phase.grid(), bodies, &mut vec![], &mut overlaps);
+        overlaps
+    }
+
+    fn update(sensor: &mut Sensor, overlaps: &[Handle]) -> Vec<SensorEvent> {
+        let mut events = vec![];
+        sensor.update(overlaps, &mut events);
+        events
+    }
+
+    #[test]
+    fn test_enter_stay_leave() {
+        let mut sensor = Sensor::new(7, Rectangle::new(20., 20., 30., 30.)).into_shared();
+        // The same body in three different places
+        let body = Arena::new().insert(());
+        let [outside, crossing, inside] = [
+            Rectangle::new(70., 70., 5., 5.),
+            Rectangle::new(45., 45., 10., 10.),
+            Rectangle::new(30., 30., 5., 5.),
+        ]
+        .map(|rec| {
+            let mut block = Block::new(rec, Vector2::zero());
+            block.set_handle(body);
+            block
+        });
+
+        let overlaps = overlaps_with(&sensor, &outside);
+        assert!(update(&mut sensor, &overlaps).is_empty());
+
+        let overlaps = overlaps_with(&sensor, &crossing);
+        assert_eq!(
+            update(&mut sensor, &overlaps),
+            [SensorEvent::Enter { sensor: 7, body }]
+        );
+
+        let overlaps = overlaps_with(&sensor, &inside);
+        assert!(update(&mut sensor, &overlaps).is_empty());
+        assert_eq!(sensor.overlapping(), [body]);
+
+        let overlaps = overlaps_with(&sensor, &outside);
+        assert_eq!(
+            update(&mut sensor, &overlaps),
+            [SensorEvent::Leave { sensor: 7, body }]
+        );
+    }
+
+    #[test]
+    fn test_filtered_body_is_ignored() {
+        let mut sensor = Sensor::new(0, Rectangle::new(20., 20., 30., 30.)).into_shared();
+        sensor.set_collision_filter(CollisionFilter::new(1, 0));
+        let mut inside = Block::new(Rectangle::new(30., 30., 5., 5.), Vector2::zero());
+        inside.set_handle(Arena::new().insert(()));
+
+        assert!(overlaps_with(&sensor, &inside).is_empty());
+    }
+
+    #[test]
+    fn test_compound_children_overlap() {
+        // L shape, the corner of the bounding box is empty
+        let mut block = Block::compound(
+            &[
+                Rectangle::new(10., 10., 10., 30.),
+                Rectangle::new(20., 30., 20., 10.),
+            ],
+            Vector2::zero(),
+        )
+        .unwrap();
+        let body = Arena::new().insert(());
+        block.set_handle(body);
+
+        let empty_corner = Sensor::new(0, Rectangle::new(30., 12., 5., 5.)).into_shared();
+        assert!(overlaps_with(&empty_corner, &block).is_empty());
+
+        let on_foot = Sensor::new(1, Rectangle::new(30., 27., 5., 5.)).into_shared();
+        assert_eq!(overlaps_with(&on_foot, &block), [body]);
+    }
+}
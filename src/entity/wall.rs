@@ -1,47 +1,139 @@
 use raylib::{
     color::Color,
-    math::Vector2,
+    math::{Rectangle, Vector2},
     prelude::{RaylibDraw, RaylibDrawHandle},
 };
 
-use crate::traits::{Draw, GridItemTrait, Sides};
+use crate::{
+    collision::{contact::Friction, filter::CollisionFilter, manifold::Manifold},
+    traits::{Draw, GridItemTrait, Sides},
+};
+
+use super::{body::BodyKind, segment::Segment};
 
-use super::{block::Block, segment::Segment};
+// Per second, the pivot moves along with the wall
+#[derive(Debug, Clone, Copy, Default)]
+pub struct WallMotion {
+    pub speed: Vector2,
+    pub angular_speed: f32,
+    pub pivot: Vector2,
+}
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Wall {
     position: Segment,
+    old_position: Segment,
+    // Sides of the thick wall, updated whenever the wall moves
+    collision_box: [Segment; 4],
+    motion: Option<WallMotion>,
+    // Points before the start and after the end when the wall is part of a chain
+    joints: [Option<Vector2>; 2],
+    one_way: bool,
     thick: f32,
+    filter: CollisionFilter,
+    restitution: f32,
+    friction: Friction,
 }
 
 impl Wall {
     #[must_use]
     pub fn new(start: Vector2, end: Vector2) -> Self {
+        let position = Segment { start, end };
+        let thick = 5.;
         Self {
-            position: Segment { start, end },
-            thick: 5.,
+            collision_box: Self::calculate_collision_box(&position, thick),
+            old_position: position.clone(),
+            position,
+            motion: None,
+            joints: [None; 2],
+            one_way: false,
+            thick,
+            filter: CollisionFilter::default(),
+            restitution: 1.,
+            friction: Friction::default(),
         }
     }
 
+    #[must_use]
+    pub fn segment(&self) -> &Segment {
+        &self.position
+    }
+
+    pub fn set_motion(&mut self, motion: Option<WallMotion>) {
+        self.motion = motion;
+    }
+
+    // One-way walls only stop blocks coming from the side the segment normal points to
+    pub fn set_one_way(&mut self, one_way: bool) {
+        self.one_way = one_way;
+    }
+
+    pub(super) fn set_joints(&mut self, previous: Option<Vector2>, next: Option<Vector2>) {
+        self.joints = [previous, next];
+    }
+
+    pub fn translate(&mut self, offset: Vector2) {
+        self.position.start += offset;
+        self.position.end += offset;
+        self.joints
+            .iter_mut()
+            .flatten()
+            .for_each(|joint| *joint += offset);
+        self.collision_box = Self::calculate_collision_box(&self.position, self.thick);
+    }
+
+    pub fn rotate(&mut self, pivot: Vector2, angle: f32) {
+        let rotate = |point: &mut Vector2| *point = pivot + (*point - pivot).rotated(angle);
+        rotate(&mut self.position.start);
+        rotate(&mut self.position.end);
+        self.joints.iter_mut().flatten().for_each(rotate);
+        self.collision_box = Self::calculate_collision_box(&self.position, self.thick);
+    }
+
+    // Like blocks, the speed is the displacement since the previous step
+    pub fn update(&mut self, delta: f32) {
+        self.old_position = self.position.clone();
+
+        if let Some(motion) = self.motion.as_mut() {
+            let offset = motion.speed * delta;
+            let (pivot, angle) = (motion.pivot, motion.angular_speed * delta);
+            motion.pivot += offset;
+            self.rotate(pivot, angle);
+            self.translate(offset);
+        }
+    }
+
+    pub fn set_restitution(&mut self, restitution: f32) {
+        self.restitution = restitution;
+    }
+
+    pub fn set_friction(&mut self, friction: Friction) {
+        self.friction = friction;
+    }
+
+    pub fn set_collision_filter(&mut self, filter: CollisionFilter) {
+        self.filter = filter;
+    }
+
     pub fn draw_debug(&self, canvas: &mut RaylibDrawHandle) {
-        self.get_collision_box().iter().for_each(|segment| {
+        self.collision_box.iter().for_each(|segment| {
             segment.draw_debug(canvas);
             segment.draw(canvas);
         });
     }
 
-    fn get_collision_box(&self) -> [Segment; 4] {
-        let delta = self.position.end - self.position.start;
+    fn calculate_collision_box(position: &Segment, thick: f32) -> [Segment; 4] {
+        let delta = position.end - position.start;
         let length = delta.length();
 
-        let scale = self.thick / (2. * length);
+        let scale = thick / (2. * length);
         let radius = Vector2::new(-scale * delta.y, scale * delta.x);
 
         let sides = [
-            self.position.start - radius,
-            self.position.start + radius,
-            self.position.end - radius,
-            self.position.end + radius,
+            position.start - radius,
+            position.start + radius,
+            position.end - radius,
+            position.end + radius,
         ];
 
         [
@@ -67,23 +159,19 @@ impl Wall {
 
 impl Sides for Wall {
     fn top(&self) -> Segment {
-        let [top, ..] = self.get_collision_box();
-        top
+        self.collision_box[0].clone()
     }
 
     fn right(&self) -> Segment {
-        let [_, right, ..] = self.get_collision_box();
-        right
+        self.collision_box[1].clone()
     }
 
     fn bottom(&self) -> Segment {
-        let [_, _, bottom, ..] = self.get_collision_box();
-        bottom
+        self.collision_box[2].clone()
     }
 
     fn left(&self) -> Segment {
-        let [_, _, _, left] = self.get_collision_box();
-        left
+        self.collision_box[3].clone()
     }
 }
 
@@ -98,10 +186,137 @@ impl Draw for Wall {
     }
 }
 
-impl<'a> PartialEq<&'a Block> for Wall {
-    fn eq(&self, _other: &&'a Block) -> bool {
-        false
+impl GridItemTrait for Wall {
+    fn collision_filter(&self) -> CollisionFilter {
+        self.filter
+    }
+
+    fn restitution(&self) -> f32 {
+        self.restitution
+    }
+
+    fn friction(&self) -> Friction {
+        self.friction
+    }
+
+    fn body_kind(&self) -> BodyKind {
+        if self.position == self.old_position {
+            BodyKind::Static
+        } else {
+            BodyKind::Kinematic
+        }
+    }
+
+    fn speed_at(&self, point: Vector2) -> Vector2 {
+        let amount = self.position.project(point);
+        self.position.point_at(amount) - self.old_position.point_at(amount)
+    }
+
+    // Inside a chain the ends of the collision box touch the next wall, blocks sliding over
+    // the joint would snag on them, so the face normal is used instead
+    fn manifold_with(&self, shape: &Rectangle, speed: Vector2) -> Option<Manifold> {
+        let manifold = Manifold::between(shape, self)?;
+
+        if self.one_way {
+            let normal = self.position.normal_unit();
+            let normal = normal.end - normal.start;
+            let old_shape = Rectangle {
+                x: shape.x - speed.x,
+                y: shape.y - speed.y,
+                ..*shape
+            };
+            let lowest = [old_shape.top(), old_shape.bottom()]
+                .iter()
+                .flat_map(|side| [side.start.dot(normal), side.end.dot(normal)])
+                .fold(f32::MAX, f32::min);
+
+            // Blocks moving away from the normal side, or that weren't on it before, pass through
+            if speed.dot(normal) > 0. || lowest < self.position.start.dot(normal) {
+                return None;
+            }
+            return Manifold::along(shape, self, normal);
+        }
+
+        let direction = (self.position.end - self.position.start).normalized();
+        let along = manifold.normal.dot(direction);
+
+        let neighbour = if along > 0.99 {
+            self.joints[0].map(|previous| Segment {
+                start: previous,
+                end: self.position.start,
+            })
+        } else if along < -0.99 {
+            self.joints[1].map(|next| Segment {
+                start: self.position.end,
+                end: next,
+            })
+        } else {
+            None
+        };
+
+        let center = shape.center();
+        match neighbour {
+            Some(neighbour) if neighbour.side_of(center) * self.position.side_of(center) >= 0. => {
+                let normal = self.position.normal_unit();
+                Manifold::along(shape, self, normal.end - normal.start)
+            }
+            _ => Some(manifold),
+        }
     }
 }
 
-impl<'a> GridItemTrait<'a> for Wall {}
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::FRAC_PI_2;
+
+    use super::*;
+
+    fn assert_near(a: Vector2, b: Vector2) {
+        assert!((a - b).length() < 1e-4, "{a:?} != {b:?}");
+    }
+
+    #[test]
+    fn test_one_way() {
+        let mut wall = Wall::new(Vector2::new(0., 50.), Vector2::new(100., 50.));
+        wall.set_one_way(true);
+
+        let from_below = Rectangle::new(40., 49., 10., 10.);
+        assert_eq!(wall.manifold_with(&from_below, Vector2::new(0., -5.)), None);
+
+        // Halfway through and falling back, it never was above the wall
+        assert_eq!(wall.manifold_with(&from_below, Vector2::new(0., 1.)), None);
+
+        let from_above = Rectangle::new(40., 42., 10., 10.);
+        let manifold = wall
+            .manifold_with(&from_above, Vector2::new(0., 5.))
+            .unwrap();
+        assert!((manifold.normal - Vector2::new(0., 1.)).length() < 1e-4);
+        assert!((manifold.depth - 4.5).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_motion() {
+        let mut wall = Wall::new(Vector2::new(0., 0.), Vector2::new(10., 0.));
+        wall.set_motion(Some(WallMotion {
+            speed: Vector2::new(0., 10.),
+            angular_speed: FRAC_PI_2,
+            pivot: Vector2::zero(),
+        }));
+        assert_eq!(wall.body_kind(), BodyKind::Static);
+
+        wall.update(1.);
+        assert_near(wall.segment().start, Vector2::new(0., 10.));
+        assert_near(wall.segment().end, Vector2::new(0., 20.));
+        assert_eq!(wall.body_kind(), BodyKind::Kinematic);
+        assert_near(wall.speed_at(Vector2::new(0., 10.)), Vector2::new(0., 10.));
+        assert_near(
+            wall.speed_at(Vector2::new(5., 20.)),
+            Vector2::new(-10., 20.),
+        );
+
+        // The pivot moved with the wall
+        wall.update(1.);
+        assert_near(wall.segment().start, Vector2::new(0., 20.));
+        assert_near(wall.segment().end, Vector2::new(-10., 20.));
+    }
+}
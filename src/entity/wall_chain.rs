@@ -0,0 +1,125 @@
+use raylib::math::Vector2;
+
+use crate::collision::{contact::Friction, filter::CollisionFilter};
+
+use super::wall::Wall;
+
+// Walls joined end to end, the joints between them are smooth
+#[derive(Debug)]
+pub struct WallChain {
+    walls: Vec<Wall>,
+    closed: bool,
+}
+
+impl WallChain {
+    // A closed chain also joins the last point with the first one
+    #[must_use]
+    pub fn new(points: &[Vector2], closed: bool) -> Self {
+        let mut ends = points
+            .windows(2)
+            .map(|pair| (pair[0], pair[1]))
+            .collect::<Vec<_>>();
+        if closed && points.len() > 2 {
+            ends.push((points[points.len() - 1], points[0]));
+        }
+
+        let count = ends.len();
+        let walls = ends
+            .iter()
+            .enumerate()
+            .map(|(index, &(start, end))| {
+                let previous = (index > 0 || closed).then(|| ends[(index + count - 1) % count].0);
+                let next = (index + 1 < count || closed).then(|| ends[(index + 1) % count].1);
+
+                let mut wall = Wall::new(start, end);
+                wall.set_joints(previous.filter(|_| count > 1), next.filter(|_| count > 1));
+                wall
+            })
+            .collect();
+
+        Self { walls, closed }
+    }
+
+    #[must_use]
+    pub fn walls(&self) -> &[Wall] {
+        &self.walls
+    }
+
+    #[must_use]
+    pub fn into_walls(self) -> Vec<Wall> {
+        self.walls
+    }
+
+    #[must_use]
+    pub fn is_closed(&self) -> bool {
+        self.closed
+    }
+
+    pub fn set_restitution(&mut self, restitution: f32) {
+        self.walls
+            .iter_mut()
+            .for_each(|wall| wall.set_restitution(restitution));
+    }
+
+    pub fn set_friction(&mut self, friction: Friction) {
+        self.walls
+            .iter_mut()
+            .for_each(|wall| wall.set_friction(friction));
+    }
+
+    pub fn set_collision_filter(&mut self, filter: CollisionFilter) {
+        self.walls
+            .iter_mut()
+            .for_each(|wall| wall.set_collision_filter(filter));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use raylib::math::Rectangle;
+
+    use crate::traits::GridItemTrait;
+
+    use super::*;
+
+    #[test]
+    fn test_segments() {
+        let points = [
+            Vector2::new(0., 0.),
+            Vector2::new(10., 0.),
+            Vector2::new(10., 10.),
+        ];
+
+        let open = WallChain::new(&points, false);
+        assert_eq!(open.walls().len(), 2);
+
+        let closed = WallChain::new(&points, true);
+        assert_eq!(closed.walls().len(), 3);
+        assert_eq!(closed.walls()[2].segment().start, Vector2::new(10., 10.));
+        assert_eq!(closed.walls()[2].segment().end, Vector2::new(0., 0.));
+    }
+
+    #[test]
+    fn test_smooth_joint() {
+        let points = [
+            Vector2::new(0., 100.),
+            Vector2::new(50., 100.),
+            Vector2::new(100., 100.),
+        ];
+        // Resting on the first wall and just reaching the end of the second one
+        let block = Rectangle::new(41., 90.5, 10., 10.);
+
+        let alone = Wall::new(points[1], points[2]);
+        assert_eq!(
+            alone.manifold_with(&block, Vector2::zero()).unwrap().normal,
+            Vector2::new(1., 0.)
+        );
+
+        let chain = WallChain::new(&points, false);
+        let manifold = chain.walls()[1]
+            .manifold_with(&block, Vector2::zero())
+            .unwrap();
+        assert!((manifold.normal - Vector2::new(0., 1.)).length() < 1e-4);
+        assert!((manifold.depth - 3.).abs() < 1e-4);
+    }
+}
@@ -0,0 +1,302 @@
+use std::{
+    fmt::{Debug, Display},
+    ops::{Add, Div, Mul, Neg, Sub},
+};
+
+use crate::geometry::Scalar;
+
+// Signed 32.32 fixed-point number. Every operation is done with integers, so the results are the
+// same on every platform and build, unlike floats going through different instructions.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct Fixed(i64);
+
+impl Fixed {
+    pub const FRACTION_BITS: u32 = 32;
+    // 2^FRACTION_BITS
+    const SCALE: f64 = 4_294_967_296.;
+
+    #[must_use]
+    pub const fn from_bits(bits: i64) -> Self {
+        Self(bits)
+    }
+
+    #[must_use]
+    pub const fn to_bits(self) -> i64 {
+        self.0
+    }
+
+    #[must_use]
+    pub const fn from_int(value: i32) -> Self {
+        Self((value as i64) << Self::FRACTION_BITS)
+    }
+
+    // Rounds to the closest representable value, scaling by a power of two is exact.
+    #[allow(clippy::cast_possible_truncation)]
+    #[must_use]
+    pub fn from_f64(value: f64) -> Self {
+        Self((value * Self::SCALE).round() as i64)
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    #[must_use]
+    pub fn to_f64(self) -> f64 {
+        self.0 as f64 / Self::SCALE
+    }
+
+    #[allow(clippy::cast_possible_truncation)]
+    #[must_use]
+    pub fn to_f32(self) -> f32 {
+        self.to_f64() as f32
+    }
+
+    // Results out of range stick to the closest bound instead of wrapping around
+    #[allow(clippy::cast_possible_truncation)]
+    fn saturate(value: i128) -> Self {
+        Self(value.clamp(i64::MIN.into(), i64::MAX.into()) as i64)
+    }
+}
+
+impl Add for Fixed {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Self(self.0.saturating_add(other.0))
+    }
+}
+
+impl Sub for Fixed {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self {
+        Self(self.0.saturating_sub(other.0))
+    }
+}
+
+impl Neg for Fixed {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        Self(self.0.saturating_neg())
+    }
+}
+
+impl Mul for Fixed {
+    type Output = Self;
+
+    // Rounds towards zero, like the division
+    fn mul(self, other: Self) -> Self {
+        Self::saturate(i128::from(self.0) * i128::from(other.0) / (1 << Self::FRACTION_BITS))
+    }
+}
+
+impl Div for Fixed {
+    type Output = Self;
+
+    // Dividing by zero gives the bound with the sign of the dividend, or zero for zero
+    fn div(self, other: Self) -> Self {
+        if other == Self::ZERO {
+            return match self.0.signum() {
+                1 => Self::MAX,
+                -1 => Self::MIN,
+                _ => Self::ZERO,
+            };
+        }
+
+        Self::saturate((i128::from(self.0) << Self::FRACTION_BITS) / i128::from(other.0))
+    }
+}
+
+impl Scalar for Fixed {
+    const ZERO: Self = Self(0);
+    const ONE: Self = Self::from_int(1);
+    const EPSILON: Self = Self(1);
+    const MIN: Self = Self(i64::MIN);
+    const MAX: Self = Self(i64::MAX);
+    // Rounded to the closest representable values
+    const PI: Self = Self(13_493_037_705);
+    const TAU: Self = Self(26_986_075_409);
+
+    fn from_f32(value: f32) -> Self {
+        Self::from_f64(value.into())
+    }
+
+    // Negative values have no root, they return zero
+    #[allow(clippy::cast_possible_truncation)]
+    #[allow(clippy::cast_sign_loss)]
+    fn sqrt(self) -> Self {
+        if self.0 <= 0 {
+            return Self::ZERO;
+        }
+
+        Self(((self.0 as u128) << Self::FRACTION_BITS).isqrt() as i64)
+    }
+
+    fn abs(self) -> Self {
+        Self(self.0.saturating_abs())
+    }
+
+    // Polynomial approximation, off by about 1e-5 radians at most
+    fn atan2(self, other: Self) -> Self {
+        let (y, x) = (self, other);
+        if x == Self::ZERO && y == Self::ZERO {
+            return Self::ZERO;
+        }
+
+        let (small, large) = if y.abs() <= x.abs() {
+            (y.abs(), x.abs())
+        } else {
+            (x.abs(), y.abs())
+        };
+        let ratio = small / large;
+        let square = ratio * ratio;
+        let mut angle = [-0.085_133, 0.180_141, -0.330_299_5, 0.999_866]
+            .into_iter()
+            .fold(Self::from_f64(0.020_835_1), |acc, coefficient| {
+                acc * square + Self::from_f64(coefficient)
+            })
+            * ratio;
+
+        if y.abs() > x.abs() {
+            angle = Self::PI / Self::from_int(2) - angle;
+        }
+        if x < Self::ZERO {
+            angle = Self::PI - angle;
+        }
+        if y < Self::ZERO {
+            angle = -angle;
+        }
+        angle
+    }
+
+    fn min(self, other: Self) -> Self {
+        Ord::min(self, other)
+    }
+
+    fn max(self, other: Self) -> Self {
+        Ord::max(self, other)
+    }
+
+    #[allow(clippy::cast_sign_loss)]
+    #[allow(clippy::cast_possible_truncation)]
+    fn to_index(self) -> usize {
+        (self.0 >> Self::FRACTION_BITS).max(0) as usize
+    }
+}
+
+impl Debug for Fixed {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Fixed({})", self.to_f64())
+    }
+}
+
+impl Display for Fixed {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        Display::fmt(&self.to_f64(), f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        entity::segment::Segment,
+        geometry::{Rect, Vec2, Vector, Verlet},
+        traits::Collision,
+    };
+
+    use super::*;
+
+    fn vec2(x: f64, y: f64) -> Vec2<Fixed> {
+        Vec2::new(Fixed::from_f64(x), Fixed::from_f64(y))
+    }
+
+    #[test]
+    fn test_arithmetic() {
+        let half = Fixed::from_f64(0.5);
+
+        assert_eq!(half + half, Fixed::ONE);
+        assert_eq!(Fixed::from_int(3) * half, Fixed::from_f64(1.5));
+        assert_eq!(Fixed::ONE / Fixed::from_int(4), Fixed::from_f64(0.25));
+        assert_eq!(Fixed::from_int(9).sqrt(), Fixed::from_int(3));
+        assert_eq!(Fixed::from_f64(-2.5).to_index(), 0);
+        assert_eq!(Fixed::from_f64(7.9).to_index(), 7);
+        assert_eq!(Fixed::PI, Fixed::from_f64(std::f64::consts::PI));
+        assert_eq!(Fixed::from_f64(-1.5) * Fixed::EPSILON, -Fixed::EPSILON);
+        assert_eq!(Fixed::from_bits(-3) / Fixed::from_int(2), -Fixed::EPSILON);
+        assert_eq!(Fixed::TAU, Fixed::from_f64(std::f64::consts::TAU));
+
+        for (y, x) in [(1., 1.), (1., -2.), (-3., -0.5), (-0.2, 4.), (5., 0.)] {
+            let angle = Fixed::from_f64(y).atan2(Fixed::from_f64(x)).to_f64();
+            assert!((angle - f64::atan2(y, x)).abs() < 2e-5);
+        }
+    }
+
+    #[test]
+    fn test_overflow_saturates() {
+        let big = Fixed::from_int(i32::MAX);
+
+        assert_eq!(Fixed::MAX + Fixed::ONE, Fixed::MAX);
+        assert_eq!(Fixed::MIN - Fixed::ONE, Fixed::MIN);
+        assert_eq!(-Fixed::MIN, Fixed::MAX);
+        assert_eq!(Fixed::MIN.abs(), Fixed::MAX);
+        assert_eq!(big * big, Fixed::MAX);
+        assert_eq!(-big * big, Fixed::MIN);
+        assert_eq!(big / Fixed::EPSILON, Fixed::MAX);
+        assert_eq!(Fixed::ONE / Fixed::ZERO, Fixed::MAX);
+        assert_eq!(-Fixed::ONE / Fixed::ZERO, Fixed::MIN);
+        assert_eq!(Fixed::ZERO / Fixed::ZERO, Fixed::ZERO);
+
+        let point = Segment {
+            start: vec2(2., 3.),
+            end: vec2(2., 3.),
+        };
+        assert_eq!(point.normal_unit(), point);
+    }
+
+    #[test]
+    fn test_segment_intersection() {
+        let segment = Segment {
+            start: vec2(0., 0.),
+            end: vec2(10., 10.),
+        };
+        let other = Segment {
+            start: vec2(0., 10.),
+            end: vec2(10., 0.),
+        };
+        let parallel = Segment {
+            start: vec2(1., 0.),
+            end: vec2(11., 10.),
+        };
+
+        assert_eq!(segment.check_collision_segment(&other), Some(vec2(5., 5.)));
+        assert_eq!(segment.check_collision_segment(&parallel), None);
+
+        let rec = Rect::new(Fixed::ZERO, Fixed::ZERO, Fixed::ONE, Fixed::ONE);
+        let touching = Rect {
+            x: Fixed::from_f64(0.5),
+            ..rec
+        };
+        assert!(rec.check_collision(&touching).into_option().is_some());
+    }
+
+    #[test]
+    fn test_same_inputs_same_bits() {
+        let run = || {
+            let mut body = Verlet::new(vec2(0., 0.));
+            body.set_speed(vec2(0.3, -1.7));
+            let gravity = vec2(0., 981.);
+            let delta = Fixed::ONE / Fixed::from_int(60);
+            for _ in 0..600 {
+                body.integrate(gravity, delta, Fixed::from_f64(0.1));
+                body.update();
+            }
+            let Vec2 { x, y } = body.position;
+            [x.to_bits(), y.to_bits(), body.speed().length().to_bits()]
+        };
+
+        let first = run();
+        assert_eq!(first, run());
+        // The body fell while keeping some of its horizontal speed
+        assert!(Fixed::from_bits(first[0]) > Fixed::from_int(50));
+        assert!(Fixed::from_bits(first[1]) > Fixed::from_int(1000));
+    }
+}
@@ -0,0 +1,379 @@
+use std::{
+    fmt::Debug,
+    ops::{Add, Div, Mul, Neg, Sub},
+};
+
+use raylib::math::Vector2;
+
+use crate::{entity::segment::Segment, traits::Sides};
+
+pub trait Scalar:
+    Copy
+    + Debug
+    + Default
+    + PartialOrd
+    + Add<Output = Self>
+    + Sub<Output = Self>
+    + Mul<Output = Self>
+    + Div<Output = Self>
+    + Neg<Output = Self>
+{
+    const ZERO: Self;
+    const ONE: Self;
+    const EPSILON: Self;
+    const MIN: Self;
+    const MAX: Self;
+    const PI: Self;
+    const TAU: Self;
+
+    fn from_f32(value: f32) -> Self;
+    #[must_use]
+    fn sqrt(self) -> Self;
+    #[must_use]
+    fn abs(self) -> Self;
+    #[must_use]
+    fn atan2(self, other: Self) -> Self;
+    #[must_use]
+    fn min(self, other: Self) -> Self;
+    #[must_use]
+    fn max(self, other: Self) -> Self;
+    // Index of the grid cell containing the value, negative values land in the first one
+    fn to_index(self) -> usize;
+
+    #[must_use]
+    fn clamp(self, min: Self, max: Self) -> Self {
+        self.max(min).min(max)
+    }
+}
+
+macro_rules! impl_scalar {
+    ($($scalar:ident),*) => {
+        $(
+            impl Scalar for $scalar {
+                const ZERO: Self = 0.;
+                const ONE: Self = 1.;
+                const EPSILON: Self = $scalar::EPSILON;
+                const MIN: Self = $scalar::MIN;
+                const MAX: Self = $scalar::MAX;
+                const PI: Self = std::$scalar::consts::PI;
+                const TAU: Self = std::$scalar::consts::TAU;
+
+                fn from_f32(value: f32) -> Self {
+                    value.into()
+                }
+
+                fn sqrt(self) -> Self {
+                    $scalar::sqrt(self)
+                }
+
+                fn abs(self) -> Self {
+                    $scalar::abs(self)
+                }
+
+                fn atan2(self, other: Self) -> Self {
+                    $scalar::atan2(self, other)
+                }
+
+                fn min(self, other: Self) -> Self {
+                    $scalar::min(self, other)
+                }
+
+                fn max(self, other: Self) -> Self {
+                    $scalar::max(self, other)
+                }
+
+                #[allow(clippy::cast_possible_truncation)]
+                #[allow(clippy::cast_sign_loss)]
+                fn to_index(self) -> usize {
+                    self as usize
+                }
+            }
+        )*
+    };
+}
+
+impl_scalar!(f32, f64);
+
+pub trait Vector:
+    Copy
+    + Debug
+    + PartialEq
+    + Add<Output = Self>
+    + Sub<Output = Self>
+    + Neg<Output = Self>
+    + Mul<Self::Scalar, Output = Self>
+    + Div<Self::Scalar, Output = Self>
+{
+    type Scalar: Scalar;
+
+    fn new(x: Self::Scalar, y: Self::Scalar) -> Self;
+    fn x(self) -> Self::Scalar;
+    fn y(self) -> Self::Scalar;
+
+    fn zero() -> Self {
+        Self::new(Self::Scalar::ZERO, Self::Scalar::ZERO)
+    }
+
+    fn dot(self, other: Self) -> Self::Scalar {
+        self.x() * other.x() + self.y() * other.y()
+    }
+
+    fn length_sqr(self) -> Self::Scalar {
+        self.dot(self)
+    }
+
+    fn length(self) -> Self::Scalar {
+        self.length_sqr().sqrt()
+    }
+
+    fn distance_to(self, other: Self) -> Self::Scalar {
+        (other - self).length()
+    }
+
+    #[must_use]
+    fn lerp(self, other: Self, amount: Self::Scalar) -> Self {
+        self + (other - self) * amount
+    }
+
+    // From 0 to a full turn, like raylib's
+    fn angle_to(self, other: Self) -> Self::Scalar {
+        let angle = (other.y() - self.y()).atan2(other.x() - self.x());
+        if angle < Self::Scalar::ZERO {
+            angle + Self::Scalar::TAU
+        } else {
+            angle
+        }
+    }
+}
+
+impl Vector for Vector2 {
+    type Scalar = f32;
+
+    fn new(x: f32, y: f32) -> Self {
+        Vector2::new(x, y)
+    }
+
+    fn x(self) -> f32 {
+        self.x
+    }
+
+    fn y(self) -> f32 {
+        self.y
+    }
+}
+
+// Same as raylib's `Vector2` for any scalar, `Vec2<f64>` keeps large coordinates precise.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Vec2<T> {
+    pub x: T,
+    pub y: T,
+}
+
+impl<T: Scalar> Vector for Vec2<T> {
+    type Scalar = T;
+
+    fn new(x: T, y: T) -> Self {
+        Self { x, y }
+    }
+
+    fn x(self) -> T {
+        self.x
+    }
+
+    fn y(self) -> T {
+        self.y
+    }
+}
+
+impl<T: Scalar> Add for Vec2<T> {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Self::new(self.x + other.x, self.y + other.y)
+    }
+}
+
+impl<T: Scalar> Sub for Vec2<T> {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self {
+        Self::new(self.x - other.x, self.y - other.y)
+    }
+}
+
+impl<T: Scalar> Neg for Vec2<T> {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        Self::new(-self.x, -self.y)
+    }
+}
+
+impl<T: Scalar> Mul<T> for Vec2<T> {
+    type Output = Self;
+
+    fn mul(self, value: T) -> Self {
+        Self::new(self.x * value, self.y * value)
+    }
+}
+
+impl<T: Scalar> Div<T> for Vec2<T> {
+    type Output = Self;
+
+    fn div(self, value: T) -> Self {
+        Self::new(self.x / value, self.y / value)
+    }
+}
+
+impl From<Vector2> for Vec2<f64> {
+    fn from(vector: Vector2) -> Self {
+        Self::new(vector.x.into(), vector.y.into())
+    }
+}
+
+// Axis aligned rectangle, what raylib's `Rectangle` is for any scalar
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Rect<T> {
+    pub x: T,
+    pub y: T,
+    pub width: T,
+    pub height: T,
+}
+
+impl<T: Scalar> Rect<T> {
+    pub fn new(x: T, y: T, width: T, height: T) -> Self {
+        Self {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    pub fn position(&self) -> Vec2<T> {
+        Vec2::new(self.x, self.y)
+    }
+
+    pub fn set_position(&mut self, position: Vec2<T>) {
+        self.x = position.x;
+        self.y = position.y;
+    }
+}
+
+impl<T: Scalar> Sides<Vec2<T>> for Rect<T> {
+    fn top(&self) -> Segment<Vec2<T>> {
+        Segment {
+            start: self.position(),
+            end: Vec2::new(self.x + self.width, self.y),
+        }
+    }
+
+    fn right(&self) -> Segment<Vec2<T>> {
+        Segment {
+            start: Vec2::new(self.x + self.width, self.y),
+            end: Vec2::new(self.x + self.width, self.y + self.height),
+        }
+    }
+
+    fn bottom(&self) -> Segment<Vec2<T>> {
+        Segment {
+            start: Vec2::new(self.x, self.y + self.height),
+            end: Vec2::new(self.x + self.width, self.y + self.height),
+        }
+    }
+
+    fn left(&self) -> Segment<Vec2<T>> {
+        Segment {
+            start: self.position(),
+            end: Vec2::new(self.x, self.y + self.height),
+        }
+    }
+}
+
+// Position and speed the way blocks keep them, the speed is the displacement of the last step.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Verlet<V> {
+    pub position: V,
+    pub old_position: V,
+}
+
+impl<V: Vector> Verlet<V> {
+    pub fn new(position: V) -> Self {
+        Self {
+            position,
+            old_position: position,
+        }
+    }
+
+    pub fn speed(&self) -> V {
+        self.position - self.old_position
+    }
+
+    pub fn set_speed(&mut self, speed: V) {
+        self.old_position = self.position - speed;
+    }
+
+    // Speed after `acceleration` was applied during `delta`, slowed down by `damping`
+    pub fn integrate(&mut self, acceleration: V, delta: V::Scalar, damping: V::Scalar) {
+        let speed =
+            (self.speed() + acceleration * delta * delta) / (V::Scalar::ONE + delta * damping);
+        self.set_speed(speed);
+    }
+
+    // Moves on at the same speed
+    pub fn update(&mut self) {
+        let speed = self.speed();
+        self.old_position = self.position;
+        self.position = self.position + speed;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        collision::grid::{Col, Grid, Row},
+        traits::Collision,
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_large_coordinates() {
+        // Past 2^24 f32 can't tell these apart anymore
+        let origin = 20_000_000.;
+        let rec = Rect::new(origin, origin, 0.5, 0.5);
+        let other = Rect::new(origin + 0.25, origin + 0.25, 0.5, 0.5);
+        let far = Rect::new(origin + 0.75, origin, 0.5, 0.5);
+
+        assert!(rec.check_collision(&other).into_option().is_some());
+        assert!(rec.check_collision(&far).into_option().is_none());
+
+        let segment = Segment {
+            start: Vec2::new(origin, origin),
+            end: Vec2::new(origin + 1., origin + 1.),
+        };
+        assert_eq!(segment.point_at(0.5), Vec2::new(origin + 0.5, origin + 0.5));
+        assert!((segment.project(Vec2::new(origin + 1., origin)) - 0.5).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn test_angle_keeps_precision() {
+        let angle = Vec2::new(0_f64, 0.).angle_to(Vec2::new(1., -1e-9));
+        assert!((angle - (std::f64::consts::TAU - 1e-9)).abs() < 1e-15);
+    }
+
+    #[test]
+    fn test_grid() {
+        let rec = Rect::new(300_000.5_f64, 450_000.5, 10., 10.);
+        let mut grid = Grid::new(50, 50, 10_000_f64);
+
+        let (rows, cols) = rec.calculate_grid_ranges(grid.spacing);
+        assert_eq!(
+            (rows.clone(), cols.clone()),
+            (Row(45)..=Row(45), Col(30)..=Col(30))
+        );
+        assert_eq!(grid.set_many(rec, rows, cols), Some(()));
+        assert_eq!(grid.get(&Row(45), &Col(30)), Some(&vec![rec]));
+        assert_eq!(rec.center(), Vec2::new(300_005.5, 450_005.5));
+    }
+}
@@ -1,13 +1,23 @@
 use collision::collision_result::CollisionResult;
 use raylib::math::{Rectangle, Vector2};
 
-use traits::{Collision, GridItemTrait, Position, Redirect, Sides};
+use geometry::Vector;
+use traits::{Collision, Position, Sides};
 
+pub mod arena;
 pub mod collision;
 pub mod entity;
-
-#[allow(clippy::return_self_not_must_use)]
+#[cfg(feature = "fixed")]
+pub mod fixed;
+pub mod geometry;
+pub mod lockstep;
+#[allow(clippy::missing_errors_doc)]
+pub mod random_world;
+
+#[allow(clippy::missing_errors_doc)]
+pub mod scene;
 pub mod traits;
+pub mod world;
 
 impl Position for Rectangle {
     fn position(&self) -> Vector2 {
@@ -20,26 +30,8 @@ impl Position for Rectangle {
     }
 }
 
-impl Redirect for Vector2 {
-    fn move_up(self) -> Self {
-        Vector2::new(self.x, self.y.abs() * -1.)
-    }
-
-    fn move_down(self) -> Self {
-        Vector2::new(self.x, self.y.abs())
-    }
-
-    fn move_right(self) -> Self {
-        Vector2::new(self.x.abs(), self.y)
-    }
-
-    fn move_left(self) -> Self {
-        Vector2::new(self.x.abs() * -1., self.y)
-    }
-}
-
-impl<'a, T: Sides> Collision<'a> for T {
-    fn check_collision(&'a self, other: &'a dyn GridItemTrait<'a>) -> CollisionResult {
+impl<V: Vector, T: Sides<V> + ?Sized> Collision<V> for T {
+    fn check_collision<S: Sides<V> + ?Sized>(&self, other: &S) -> CollisionResult<V> {
         let segments = [self.top(), self.right(), self.bottom(), self.left()];
         let other_segments = [other.top(), other.right(), other.bottom(), other.left()];
 
@@ -49,9 +41,9 @@ impl<'a, T: Sides> Collision<'a> for T {
                 segments
                     .iter()
                     .map(|segment| segment.check_collision_segment(other))
-                    .collect::<CollisionResult>()
+                    .collect::<CollisionResult<V>>()
             })
-            .collect::<CollisionResult>()
+            .collect::<CollisionResult<V>>()
     }
 }
 
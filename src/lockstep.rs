@@ -0,0 +1,274 @@
+use crate::{
+    collision::{
+        grid::Grid,
+        manifold::Manifold,
+        solver::{ContactConstraint, PositionCorrection},
+    },
+    geometry::{Rect, Scalar, Vec2, Vector, Verlet},
+    traits::{RigidBody, Sides},
+};
+
+// Axis aligned box stepped by `LockstepWorld`, bodies without mass never move.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Body<T> {
+    motion: Verlet<Vec2<T>>,
+    size: Vec2<T>,
+    inverse_mass: T,
+    restitution: T,
+}
+
+impl<T: Scalar> Body<T> {
+    pub fn new(rec: Rect<T>, speed: Vec2<T>) -> Self {
+        let mut motion = Verlet::new(rec.position());
+        motion.set_speed(speed);
+        Self {
+            motion,
+            size: Vec2::new(rec.width, rec.height),
+            inverse_mass: T::ONE,
+            restitution: T::ONE,
+        }
+    }
+
+    pub fn fixed(rec: Rect<T>) -> Self {
+        Self {
+            inverse_mass: T::ZERO,
+            ..Self::new(rec, Vec2::zero())
+        }
+    }
+
+    pub fn rec(&self) -> Rect<T> {
+        let Vec2 { x, y } = self.motion.position;
+        Rect::new(x, y, self.size.x, self.size.y)
+    }
+
+    pub fn position(&self) -> Vec2<T> {
+        self.motion.position
+    }
+
+    pub fn speed(&self) -> Vec2<T> {
+        self.motion.speed()
+    }
+
+    pub fn set_mass(&mut self, mass: T) {
+        self.inverse_mass = T::ONE / mass;
+    }
+
+    pub fn set_restitution(&mut self, restitution: T) {
+        self.restitution = restitution;
+    }
+
+    pub fn translate(&mut self, offset: Vec2<T>) {
+        self.motion.position = self.motion.position + offset;
+        self.motion.old_position = self.motion.old_position + offset;
+    }
+
+    fn is_fixed(&self) -> bool {
+        self.inverse_mass == T::ZERO
+    }
+}
+
+impl<T: Scalar> RigidBody<Vec2<T>> for Body<T> {
+    fn inverse_mass(&self) -> T {
+        self.inverse_mass
+    }
+
+    fn speed(&self) -> Vec2<T> {
+        self.speed()
+    }
+
+    fn set_speed(&mut self, speed: Vec2<T>) {
+        self.motion.set_speed(speed);
+    }
+
+    fn translate(&mut self, offset: Vec2<T>) {
+        self.translate(offset);
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Constraint<T: Scalar> {
+    body: usize,
+    other: usize,
+    manifold: Manifold<Vec2<T>>,
+    solver: ContactConstraint<Vec2<T>>,
+}
+
+// Same steps and solver as `World` for the scalar `T`, limited to axis aligned boxes: there are
+// no walls, joints, friction, sleep, sensors or contact events. With `fixed::Fixed` nothing goes
+// through floats, two machines stepping the same bodies get bit for bit the same positions and
+// speeds.
+#[derive(Debug)]
+pub struct LockstepWorld<T: Scalar> {
+    pub bodies: Vec<Body<T>>,
+    pub gravity: Vec2<T>,
+    pub solver_iterations: usize,
+    pub resting_speed: T,
+    pub position_correction: PositionCorrection<T>,
+    grid: Grid<usize, T>,
+    keys: Vec<usize>,
+    constraints: Vec<Constraint<T>>,
+}
+
+impl<T: Scalar> LockstepWorld<T> {
+    pub fn new(rows: usize, cols: usize, spacing: T) -> Self {
+        Self {
+            bodies: vec![],
+            gravity: Vec2::zero(),
+            solver_iterations: 8,
+            resting_speed: T::from_f32(0.5),
+            position_correction: PositionCorrection::default(),
+            grid: Grid::new(rows, cols, spacing),
+            keys: vec![],
+            constraints: vec![],
+        }
+    }
+
+    pub fn step(&mut self, delta: T) {
+        for body in self.bodies.iter_mut().filter(|body| !body.is_fixed()) {
+            body.motion.integrate(self.gravity, delta, T::ZERO);
+        }
+
+        self.find_constraints();
+        for _ in 0..self.solver_iterations {
+            for constraint in &mut self.constraints {
+                if let Ok([body, other]) = self
+                    .bodies
+                    .get_disjoint_mut([constraint.body, constraint.other])
+                {
+                    constraint.solver.apply(body, Some(other));
+                }
+            }
+        }
+
+        for body in &mut self.bodies {
+            body.motion.update();
+        }
+        for constraint in &self.constraints {
+            if let Ok([body, other]) = self
+                .bodies
+                .get_disjoint_mut([constraint.body, constraint.other])
+            {
+                self.position_correction
+                    .apply(body, Some(other), &constraint.manifold);
+            }
+        }
+    }
+
+    // Pairs come out sorted by body index, so every machine solves them in the same order
+    fn find_constraints(&mut self) {
+        let spacing = self.grid.spacing;
+        self.grid.clear();
+        for (index, body) in self.bodies.iter().enumerate() {
+            let (rows, cols) = body.rec().calculate_grid_ranges(spacing);
+            self.grid.set_many(index, rows, cols);
+        }
+
+        self.constraints.clear();
+        for (index, body) in self.bodies.iter().enumerate() {
+            let (rows, cols) = body.rec().calculate_neighbourhood(spacing);
+            self.keys.clear();
+            self.keys.extend(
+                self.grid
+                    .iter_many(rows, cols)
+                    .filter(|&&other| other > index),
+            );
+            self.keys.sort_unstable();
+            self.keys.dedup();
+
+            for &other_index in &self.keys {
+                let other = &self.bodies[other_index];
+                if body.is_fixed() && other.is_fixed() {
+                    continue;
+                }
+                let Some(manifold) = Manifold::between(&body.rec(), &other.rec()) else {
+                    continue;
+                };
+
+                let solver = ContactConstraint::along(
+                    manifold.normal,
+                    Vec2::zero(),
+                    body.restitution.min(other.restitution),
+                    body,
+                    Some(other),
+                    self.resting_speed,
+                );
+                self.constraints.push(Constraint {
+                    body: index,
+                    other: other_index,
+                    manifold,
+                    solver,
+                });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A floor, a stack of boxes falling on it and a heavier bouncing box next to them
+    fn world<T: Scalar>(value: impl Fn(f32) -> T) -> LockstepWorld<T> {
+        let vec2 = |x, y| Vec2::new(value(x), value(y));
+        let rec = |x, y, size| Rect::new(value(x), value(y), value(size), value(size));
+
+        let mut world = LockstepWorld::new(20, 20, value(10.));
+        world.gravity = vec2(0., 500.);
+        world.bodies.push(Body::fixed(Rect::new(
+            value(0.),
+            value(150.),
+            value(200.),
+            value(10.),
+        )));
+        for y in [120., 105., 90.] {
+            let mut body = Body::new(rec(90., y, 10.), Vec2::zero());
+            body.set_restitution(value(0.));
+            world.bodies.push(body);
+        }
+        let mut thrown = Body::new(rec(20., 40., 8.), vec2(0., 2.));
+        thrown.set_mass(value(2.));
+        thrown.set_restitution(value(0.5));
+        world.bodies.push(thrown);
+        world
+    }
+
+    #[test]
+    fn test_bodies_land_on_the_floor() {
+        let mut world = world(|value| value);
+        for _ in 0..600 {
+            world.step(1. / 60.);
+        }
+
+        for (body, floor) in world.bodies[1..].iter().zip([150., 140., 130., 150.]) {
+            let bottom = body.position().y + body.rec().height;
+            assert!((bottom - floor).abs() < 1.);
+            assert!(body.speed().length() < 0.05);
+        }
+    }
+
+    #[cfg(feature = "fixed")]
+    #[test]
+    fn test_fixed_steps_are_bit_identical() {
+        use crate::fixed::Fixed;
+
+        let run = || {
+            let mut world = world(|value| Fixed::from_f64(value.into()));
+            let delta = Fixed::ONE / Fixed::from_int(60);
+            for _ in 0..600 {
+                world.step(delta);
+            }
+            world
+                .bodies
+                .iter()
+                .flat_map(|body| [body.position(), body.speed()])
+                .flat_map(|Vec2 { x, y }| [x.to_bits(), y.to_bits()])
+                .collect::<Vec<_>>()
+        };
+
+        let first = run();
+        assert_eq!(first, run());
+        // The bouncing box came to rest on the floor
+        let bottom = Fixed::from_bits(first[first.len() - 3]) + Fixed::from_int(8);
+        assert!((bottom - Fixed::from_int(150)).abs() < Fixed::ONE);
+    }
+}
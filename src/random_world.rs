@@ -0,0 +1,143 @@
+use clap::Args;
+use raylib::math::Vector2;
+
+use crate::{
+    entity::{block::Block, wall_chain::WallChain},
+    world::World,
+};
+
+/// Random world options, shared by the simulation and headless binaries
+#[derive(Debug, Clone, Args)]
+pub struct RandomWorld {
+    /// Number of random blocks
+    #[arg(long, default_value_t = 1200)]
+    pub blocks: usize,
+
+    /// Smallest block side, in pixels
+    #[arg(long, default_value_t = 15, value_parser = clap::value_parser!(i32).range(1..))]
+    pub min_block_size: i32,
+
+    /// Largest block side, in pixels
+    #[arg(long, default_value_t = 15, value_parser = clap::value_parser!(i32).range(1..))]
+    pub max_block_size: i32,
+
+    /// World width, in pixels
+    #[arg(long, default_value_t = 15 * 16 * 6, value_parser = clap::value_parser!(i32).range(1..))]
+    pub width: i32,
+
+    /// World height, in pixels
+    #[arg(long, default_value_t = 15 * 9 * 6, value_parser = clap::value_parser!(i32).range(1..))]
+    pub height: i32,
+
+    /// Grid cell size, in pixels [default: twice the largest block size]
+    #[arg(long, value_parser = clap::value_parser!(i32).range(1..))]
+    pub spacing: Option<i32>,
+}
+
+impl RandomWorld {
+    #[must_use]
+    pub fn spacing(&self) -> i32 {
+        self.spacing.unwrap_or(self.max_block_size * 2)
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if self.min_block_size > self.max_block_size {
+            return Err(format!(
+                "the minimum block size ({}) is larger than the maximum ({})",
+                self.min_block_size, self.max_block_size
+            ));
+        }
+
+        let room = self.width.min(self.height) - 2 * Block::PADDING;
+        if self.max_block_size >= room {
+            return Err(format!(
+                "blocks of size {} don't fit in a {}x{} world",
+                self.max_block_size, self.width, self.height
+            ));
+        }
+
+        Ok(())
+    }
+
+    // `get_random` returns a value between both ends, included. The options must be valid, the last
+    // row and column of the grid can go past the world like in `Scene::build`.
+    #[allow(clippy::cast_possible_truncation)]
+    #[allow(clippy::cast_precision_loss)]
+    #[allow(clippy::cast_sign_loss)]
+    pub fn build<F>(&self, mut get_random: F) -> World
+    where
+        F: FnMut(i32, i32) -> i32,
+    {
+        let Self { width, height, .. } = *self;
+        let spacing = self.spacing() as f32;
+        let rows = (height as f32 / spacing).ceil() as usize;
+        let cols = (width as f32 / spacing).ceil() as usize;
+        let mut world = World::new(rows, cols, spacing);
+
+        for _ in 0..self.blocks {
+            let size = get_random(self.min_block_size, self.max_block_size);
+            world.add_block(Block::new_random(size, width, height, |min, max| {
+                get_random(min, max) as f32
+            }));
+        }
+
+        let (widthf, heightf) = (width as f32, height as f32);
+        let arena = [
+            Vector2::zero(),
+            Vector2::new(widthf, 0.),
+            Vector2::new(widthf, heightf),
+            Vector2::new(0., heightf),
+        ];
+        world.walls = WallChain::new(&arena, true).into_walls();
+        world
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options() -> RandomWorld {
+        RandomWorld {
+            blocks: 10,
+            min_block_size: 10,
+            max_block_size: 20,
+            width: 250,
+            height: 100,
+            spacing: None,
+        }
+    }
+
+    #[test]
+    fn test_validate() {
+        assert_eq!(options().validate(), Ok(()));
+
+        let sizes = RandomWorld {
+            min_block_size: 30,
+            ..options()
+        };
+        assert!(sizes.validate().is_err());
+
+        let small = RandomWorld {
+            height: 50,
+            ..options()
+        };
+        assert!(small.validate().is_err());
+    }
+
+    #[test]
+    fn test_build_rounds_the_grid_up() {
+        let mut sizes = vec![];
+        let world = options().build(|min, max| {
+            if min == 10 {
+                sizes.push(max);
+            }
+            i32::midpoint(min, max)
+        });
+
+        assert_eq!(world.blocks().len(), 10);
+        assert_eq!((world.grid().rows(), world.grid().cols()), (3, 7));
+        assert_eq!(sizes, [20; 10]);
+        assert_eq!(world.walls.len(), 4);
+    }
+}
@@ -0,0 +1,308 @@
+use std::{fmt::Display, fs, io, path::Path, str::FromStr};
+
+use raylib::{
+    color::Color,
+    math::{Rectangle, Vector2},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    entity::{block::Block, wall::Wall},
+    traits::Position,
+    world::World,
+};
+
+// Human editable description of a world, stored as RON.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Scene {
+    pub width: f32,
+    pub height: f32,
+    pub spacing: f32,
+    #[serde(default)]
+    pub blocks: Vec<BlockDescription>,
+    #[serde(default)]
+    pub walls: Vec<WallDescription>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BlockDescription {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+    #[serde(default = "BlockDescription::default_color")]
+    pub color: (u8, u8, u8, u8),
+    #[serde(default)]
+    pub acceleration: (f32, f32),
+    #[serde(default)]
+    pub speed: (f32, f32),
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WallDescription {
+    pub start: (f32, f32),
+    pub end: (f32, f32),
+}
+
+#[derive(Debug)]
+pub enum SceneError {
+    Io(io::Error),
+    Parse(ron::error::SpannedError),
+    Write(ron::Error),
+    // Parsed, but the world can't be built from it
+    Invalid(String),
+}
+
+impl Scene {
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, SceneError> {
+        fs::read_to_string(path)?.parse()
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), SceneError> {
+        fs::write(path, self.to_ron()?)?;
+        Ok(())
+    }
+
+    pub fn to_ron(&self) -> Result<String, SceneError> {
+        let config = ron::ser::PrettyConfig::new().struct_names(false);
+        Ok(ron::ser::to_string_pretty(self, config)?)
+    }
+
+    // The grid and the blocks need finite, positive sizes, `FromStr` and `load` check them
+    pub fn validate(&self) -> Result<(), SceneError> {
+        let positive = |value: f32| value.is_finite() && value > 0.;
+        let sizes = [
+            ("width", self.width),
+            ("height", self.height),
+            ("spacing", self.spacing),
+        ];
+        if let Some((name, value)) = sizes.into_iter().find(|&(_, value)| !positive(value)) {
+            return Err(SceneError::Invalid(format!(
+                "the {name} ({value}) isn't a positive number"
+            )));
+        }
+
+        let invalid = self
+            .blocks
+            .iter()
+            .position(|block| !positive(block.width) || !positive(block.height));
+        if let Some(index) = invalid {
+            let BlockDescription { width, height, .. } = self.blocks[index];
+            return Err(SceneError::Invalid(format!(
+                "block {index} has a size of {width}x{height}"
+            )));
+        }
+
+        Ok(())
+    }
+
+    // The grid covers the whole scene, the last row and column can go past it
+    #[allow(clippy::cast_possible_truncation)]
+    #[allow(clippy::cast_sign_loss)]
+    #[must_use]
+    pub fn build(&self) -> World {
+        let rows = (self.height / self.spacing).ceil() as usize;
+        let cols = (self.width / self.spacing).ceil() as usize;
+        let mut world = World::new(rows, cols, self.spacing);
+
+        world.walls = self
+            .walls
+            .iter()
+            .map(|wall| Wall::new(wall.start.into(), wall.end.into()))
+            .collect();
+        for description in &self.blocks {
+            world.add_block(description.build());
+        }
+
+        world
+    }
+
+    // Compound blocks are saved as their bounding rectangle
+    #[allow(clippy::cast_precision_loss)]
+    pub fn from_world(world: &World) -> Self {
+        let grid = world.grid();
+
+        Self {
+            width: grid.cols() as f32 * grid.spacing,
+            height: grid.rows() as f32 * grid.spacing,
+            spacing: grid.spacing,
+            blocks: world
+                .blocks()
+                .values()
+                .map(BlockDescription::from_block)
+                .collect(),
+            walls: world
+                .walls
+                .iter()
+                .map(|wall| WallDescription {
+                    start: pair(wall.segment().start),
+                    end: pair(wall.segment().end),
+                })
+                .collect(),
+        }
+    }
+}
+
+impl FromStr for Scene {
+    type Err = SceneError;
+
+    fn from_str(source: &str) -> Result<Self, Self::Err> {
+        let scene: Self = ron::from_str(source)?;
+        scene.validate()?;
+        Ok(scene)
+    }
+}
+
+impl BlockDescription {
+    fn default_color() -> (u8, u8, u8, u8) {
+        let Color { r, g, b, a } = Color::BLACK;
+        (r, g, b, a)
+    }
+
+    #[must_use]
+    pub fn build(&self) -> Block {
+        let (r, g, b, a) = self.color;
+        let mut block = Block::new(
+            Rectangle::new(self.x, self.y, self.width, self.height),
+            self.acceleration.into(),
+        );
+        block.set_color(Color::new(r, g, b, a));
+        block.set_speed(self.speed.into());
+        block
+    }
+
+    #[must_use]
+    pub fn from_block(block: &Block) -> Self {
+        let Vector2 { x, y } = block.position();
+        let rec = block.rec();
+        let Color { r, g, b, a } = block.color();
+
+        Self {
+            x,
+            y,
+            width: rec.width,
+            height: rec.height,
+            color: (r, g, b, a),
+            acceleration: pair(block.acc()),
+            speed: pair(block.speed()),
+        }
+    }
+}
+
+fn pair(Vector2 { x, y }: Vector2) -> (f32, f32) {
+    (x, y)
+}
+
+impl Display for SceneError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io(error) => write!(f, "couldn't access the scene file: {error}"),
+            Self::Parse(error) => write!(f, "invalid scene: {error}"),
+            Self::Write(error) => write!(f, "couldn't write the scene: {error}"),
+            Self::Invalid(message) => write!(f, "invalid scene: {message}"),
+        }
+    }
+}
+
+impl std::error::Error for SceneError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(error) => Some(error),
+            Self::Parse(error) => Some(error),
+            Self::Write(error) => Some(error),
+            Self::Invalid(_) => None,
+        }
+    }
+}
+
+impl From<io::Error> for SceneError {
+    fn from(error: io::Error) -> Self {
+        Self::Io(error)
+    }
+}
+
+impl From<ron::error::SpannedError> for SceneError {
+    fn from(error: ron::error::SpannedError) -> Self {
+        Self::Parse(error)
+    }
+}
+
+impl From<ron::Error> for SceneError {
+    fn from(error: ron::Error) -> Self {
+        Self::Write(error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SCENE: &str = "(
+        width: 200,
+        height: 100,
+        spacing: 20,
+        blocks: [
+            (x: 10, y: 20, width: 5, height: 5, acceleration: (100, 0)),
+            (x: 50, y: 20, width: 10, height: 5, color: (255, 0, 0, 255), speed: (0, 2)),
+        ],
+        walls: [(start: (0, 90), end: (200, 90))],
+    )";
+
+    #[test]
+    fn test_build() {
+        let scene = SCENE.parse::<Scene>().unwrap();
+        let world = scene.build();
+
+        assert_eq!((world.grid().rows(), world.grid().cols()), (5, 10));
+        assert_eq!(world.walls.len(), 1);
+        let blocks = world.blocks().values().collect::<Vec<_>>();
+        assert_eq!(blocks.len(), 2);
+        assert_eq!(blocks[0].acc(), Vector2::new(100., 0.));
+        assert_eq!(blocks[0].color(), Color::BLACK);
+        assert_eq!(blocks[1].color(), Color::new(255, 0, 0, 255));
+        assert_eq!(blocks[1].speed(), Vector2::new(0., 2.));
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let scene = SCENE.parse::<Scene>().unwrap();
+        let saved = Scene::from_world(&scene.build());
+        assert_eq!(saved, scene);
+
+        let path = std::env::temp_dir().join(format!("scene-{}.ron", std::process::id()));
+        scene.save(&path).unwrap();
+        let loaded = Scene::load(&path);
+        fs::remove_file(&path).unwrap();
+        assert_eq!(loaded.unwrap(), scene);
+
+        assert!(matches!(
+            "(width: 10)".parse::<Scene>(),
+            Err(SceneError::Parse(_))
+        ));
+        assert!(matches!(Scene::load("missing.ron"), Err(SceneError::Io(_))));
+    }
+
+    #[test]
+    fn test_invalid_sizes() {
+        assert!(matches!(
+            "(width: 10, height: 10, spacing: 0)".parse::<Scene>(),
+            Err(SceneError::Invalid(_))
+        ));
+        assert!(matches!(
+            "(width: -10, height: 10, spacing: 5)".parse::<Scene>(),
+            Err(SceneError::Invalid(_))
+        ));
+        assert!(matches!(
+            "(width: 10, height: 10, spacing: 5, blocks: [(x: 0, y: 0, width: 0, height: 2)])"
+                .parse::<Scene>(),
+            Err(SceneError::Invalid(_))
+        ));
+    }
+
+    #[test]
+    fn test_example_scene() {
+        let scene = include_str!("../scenes/box.ron").parse::<Scene>().unwrap();
+
+        assert_eq!(scene.build().blocks().len(), 4);
+    }
+}
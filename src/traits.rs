@@ -1,38 +1,44 @@
 use std::{fmt::Debug, ops::RangeInclusive};
 
-use raylib::{math::Vector2, prelude::RaylibDraw};
+use raylib::{
+    math::{Rectangle, Vector2},
+    prelude::RaylibDraw,
+};
 
 use crate::{
+    arena::Handle,
     collision::{
         collision_result::CollisionResult,
+        contact::{Contact, Friction},
+        filter::CollisionFilter,
         grid::{Col, Row},
+        manifold::Manifold,
     },
-    entity::{block::Block, segment::Segment},
+    entity::{block::Block, body::BodyKind, segment::Segment},
+    geometry::{Scalar, Vector},
 };
 
-pub trait Sides {
-    fn top(&self) -> Segment;
-    fn right(&self) -> Segment;
-    fn bottom(&self) -> Segment;
-    fn left(&self) -> Segment;
+pub trait Sides<V: Vector = Vector2> {
+    fn top(&self) -> Segment<V>;
+    fn right(&self) -> Segment<V>;
+    fn bottom(&self) -> Segment<V>;
+    fn left(&self) -> Segment<V>;
 
-    fn aabb(&self) -> Segment {
-        let corners = [self.top(), self.right(), self.bottom(), self.left()]
+    fn aabb(&self) -> Segment<V> {
+        let sides = [self.top(), self.right(), self.bottom(), self.left()];
+        let corners = sides
             .iter()
-            .flat_map(|segment| [segment.start, segment.end])
-            .collect::<Vec<_>>();
+            .flat_map(|segment| [segment.start, segment.end]);
 
         let min = corners
-            .iter()
-            .fold(Vector2::new(f32::MAX, f32::MAX), |acc, cur| {
-                Vector2::new(acc.x.min(cur.x), acc.y.min(cur.y))
+            .clone()
+            .fold(V::new(V::Scalar::MAX, V::Scalar::MAX), |acc, cur| {
+                V::new(acc.x().min(cur.x()), acc.y().min(cur.y()))
             });
 
-        let max = corners
-            .iter()
-            .fold(Vector2::new(f32::MIN, f32::MIN), |acc, cur| {
-                Vector2::new(acc.x.max(cur.x), acc.y.max(cur.y))
-            });
+        let max = corners.fold(V::new(V::Scalar::MIN, V::Scalar::MIN), |acc, cur| {
+            V::new(acc.x().max(cur.x()), acc.y().max(cur.y()))
+        });
 
         Segment {
             start: min,
@@ -40,23 +46,51 @@ pub trait Sides {
         }
     }
 
+    fn center(&self) -> V {
+        [self.top(), self.right(), self.bottom(), self.left()]
+            .iter()
+            .fold(V::zero(), |acc, segment| acc + segment.start + segment.end)
+            / V::Scalar::from_f32(8.)
+    }
+
+    fn contains_point(&self, point: V) -> bool {
+        let center = self.center();
+
+        [self.top(), self.right(), self.bottom(), self.left()]
+            .iter()
+            .all(|segment| segment.side_of(point) * segment.side_of(center) >= V::Scalar::ZERO)
+    }
+
     // TODO: create an alternative method that returns Iterator<(Row, Col)>.
     // A thin, large and rotated object can create a huge empty area to check in the grid.
-    #[allow(clippy::cast_possible_truncation)]
-    #[allow(clippy::cast_sign_loss)]
-    fn calculate_grid_ranges(&self, spacing: f32) -> (RangeInclusive<Row>, RangeInclusive<Col>) {
+    fn calculate_grid_ranges(
+        &self,
+        spacing: V::Scalar,
+    ) -> (RangeInclusive<Row>, RangeInclusive<Col>) {
         let aabb = self.aabb();
-        let width = aabb.end.x - aabb.start.x;
-        let height = aabb.end.y - aabb.start.y;
+        let width = aabb.end.x() - aabb.start.x();
+        let height = aabb.end.y() - aabb.start.y();
 
-        let col_start = (aabb.start.x / spacing) as usize;
-        let col_end = ((aabb.start.x + width) / spacing) as usize;
+        let col_start = (aabb.start.x() / spacing).to_index();
+        let col_end = ((aabb.start.x() + width) / spacing).to_index();
 
-        let row_start = (aabb.start.y / spacing) as usize;
-        let row_end = ((aabb.start.y + height) / spacing) as usize;
+        let row_start = (aabb.start.y() / spacing).to_index();
+        let row_end = ((aabb.start.y() + height) / spacing).to_index();
 
         (Row(row_start)..=Row(row_end), Col(col_start)..=Col(col_end))
     }
+
+    // The grid ranges grown by one cell on every side, an item touching the border of a cell can be
+    // stored in the next one
+    fn calculate_neighbourhood(
+        &self,
+        spacing: V::Scalar,
+    ) -> (RangeInclusive<Row>, RangeInclusive<Col>) {
+        let (rows, cols) = self.calculate_grid_ranges(spacing);
+        let rows = Row(rows.start().0.saturating_sub(1))..=Row(rows.end().0.saturating_add(1));
+        let cols = Col(cols.start().0.saturating_sub(1))..=Col(cols.end().0.saturating_add(1));
+        (rows, cols)
+    }
 }
 
 pub trait Position {
@@ -64,19 +98,93 @@ pub trait Position {
     fn set_position(&mut self, new_position: Vector2);
 }
 
-pub trait Redirect {
-    fn move_up(self) -> Self;
-    fn move_down(self) -> Self;
-    fn move_right(self) -> Self;
-    fn move_left(self) -> Self;
+// What the solver needs from a body, speeds are displacements per step
+pub trait RigidBody<V: Vector = Vector2> {
+    // Zero for bodies that can't be moved
+    fn inverse_mass(&self) -> V::Scalar;
+    fn speed(&self) -> V;
+    fn set_speed(&mut self, speed: V);
+    // Moves the body keeping its speed
+    fn translate(&mut self, offset: V);
 }
 
-pub trait Collision<'a> {
-    fn check_collision(&'a self, b: &'a dyn GridItemTrait<'a>) -> CollisionResult;
+pub trait Collision<V: Vector = Vector2> {
+    fn check_collision<S: Sides<V> + ?Sized>(&self, other: &S) -> CollisionResult<V>;
 }
 
 pub trait Draw {
     fn draw(&self, canvas: &mut impl RaylibDraw);
 }
 
-pub trait GridItemTrait<'a>: Sides + PartialEq<&'a Block> + Debug {}
+pub trait GridItemTrait: Sides + Debug {
+    fn collision_filter(&self) -> CollisionFilter {
+        CollisionFilter::default()
+    }
+
+    fn is_sensor(&self) -> bool {
+        false
+    }
+
+    // Only bodies stored in a world have a handle
+    fn handle(&self) -> Option<Handle> {
+        None
+    }
+
+    fn body_kind(&self) -> BodyKind {
+        BodyKind::Static
+    }
+
+    // Displacement during the last step of the body point at `point`
+    fn speed_at(&self, _point: Vector2) -> Vector2 {
+        Vector2::zero()
+    }
+
+    // Normal pointing from `shape`, moving at `speed`, towards this item
+    fn manifold_with(&self, shape: &Rectangle, _speed: Vector2) -> Option<Manifold> {
+        Manifold::between(shape, self)
+    }
+
+    // Compound bodies report one manifold per child shape touching `shape`, with its index
+    fn child_manifolds(
+        &self,
+        shape: &Rectangle,
+        speed: Vector2,
+        found: &mut dyn FnMut(usize, Manifold),
+    ) {
+        if let Some(manifold) = self.manifold_with(shape, speed) {
+            found(0, manifold);
+        }
+    }
+
+    fn restitution(&self) -> f32 {
+        1.
+    }
+
+    fn friction(&self) -> Friction {
+        Friction::default()
+    }
+}
+
+pub trait ContactHook {
+    // Called for every touching pair before the response, returning false ignores the contact.
+    fn on_contact<'a>(
+        &self,
+        block: &'a Block,
+        other: &'a dyn GridItemTrait,
+        contact: &mut Contact,
+    ) -> bool;
+}
+
+impl<F> ContactHook for F
+where
+    F: for<'a> Fn(&'a Block, &'a dyn GridItemTrait, &mut Contact) -> bool,
+{
+    fn on_contact<'a>(
+        &self,
+        block: &'a Block,
+        other: &'a dyn GridItemTrait,
+        contact: &mut Contact,
+    ) -> bool {
+        self(block, other, contact)
+    }
+}
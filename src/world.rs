@@ -0,0 +1,1309 @@
+use std::{
+    collections::{HashMap, HashSet},
+    ops::Range,
+    time::{Duration, Instant},
+};
+
+use raylib::{color::Color, math::Vector2, prelude::RaylibDraw};
+
+use crate::{
+    arena::{Arena, Handle},
+    collision::{
+        broad_phase::{Bodies, BroadPhase, GridKey},
+        contact::{Contact, ContactEvent, ContactTracker},
+        grid::Grid,
+        joint::Joint,
+        manifold::Manifold,
+        solver::{with_pair, ContactConstraint, PositionCorrection},
+    },
+    entity::{
+        block::{Block, NarrowPhaseCounts},
+        body::BodyKind,
+        sensor::{Sensor, SensorEvent},
+        wall::Wall,
+    },
+    traits::{ContactHook, Draw, GridItemTrait, Sides},
+};
+
+#[derive(Debug, Default)]
+pub struct StepEvents {
+    // Walls are reported too, sensors never touch anything
+    pub contacts: Vec<ContactEvent<GridKey>>,
+    pub sensors: Vec<SensorEvent>,
+}
+
+// How the last step went, the update covers integrating the forces, solving and moving the bodies
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct StepMetrics {
+    pub grid_build: Duration,
+    pub broad_phase: Duration,
+    pub narrow_phase: Duration,
+    pub update: Duration,
+    // Bodies the blocks found near them, a pair is counted by both blocks
+    pub candidate_pairs: usize,
+    // Candidates tested by the narrow phase, pairs that can't move and sensors are skipped
+    pub tested_pairs: usize,
+    // `check_collision` calls of the narrow phase, one for every pair touching
+    pub collision_checks: usize,
+}
+
+// Everything that changes while stepping and the grid size. Solver settings and the contact hook
+// aren't part of it, restoring keeps the current ones.
+#[derive(Debug, Clone)]
+pub struct Snapshot {
+    blocks: Arena<Block>,
+    walls: Vec<Wall>,
+    sensors: Vec<Sensor>,
+    joints: Vec<Joint>,
+    gravity: Vector2,
+    contacts: ContactTracker<GridKey>,
+    rows: usize,
+    cols: usize,
+    spacing: f32,
+}
+
+impl Snapshot {
+    #[must_use]
+    pub fn blocks(&self) -> &Arena<Block> {
+        &self.blocks
+    }
+
+    #[must_use]
+    pub fn walls(&self) -> &[Wall] {
+        &self.walls
+    }
+}
+
+pub struct World {
+    pub walls: Vec<Wall>,
+    pub sensors: Vec<Sensor>,
+    pub joints: Vec<Joint>,
+    pub gravity: Vector2,
+    pub solver_iterations: usize,
+    pub resting_speed: f32,
+    pub position_correction: PositionCorrection,
+    // Displacement per step under which blocks start falling asleep
+    pub sleep_speed: f32,
+    pub time_to_sleep: f32,
+    pub draw_grid: bool,
+    blocks: Arena<Block>,
+    broad_phase: BroadPhase,
+    contacts: ContactTracker<GridKey>,
+    contact_hook: Option<Box<dyn ContactHook>>,
+    metrics: StepMetrics,
+    events: StepEvents,
+    scratch: Scratch,
+}
+
+// Buffers reused by every step
+#[derive(Default)]
+struct Scratch {
+    keys: Vec<GridKey>,
+    // Candidates of all the blocks, each one has a range of them
+    candidates: Vec<GridKey>,
+    ranges: Vec<(Handle, Range<usize>)>,
+    contacts: HashMap<Handle, Vec<Contact>>,
+    // Blocks moving at the start of the step
+    moving: HashSet<Handle>,
+    // Bodies overlapping the sensors, each sensor has a range of them
+    overlaps: Vec<Handle>,
+    overlap_ranges: Vec<Range<usize>>,
+    // Blocks touching a moving wall
+    touched: Vec<Handle>,
+    // Contacts to solve, with the index of the contact in the list of the first block
+    pairs: Vec<(Handle, Option<Handle>, usize)>,
+    constraints: Vec<(Handle, Option<Handle>, ContactConstraint)>,
+}
+
+impl World {
+    #[must_use]
+    pub fn new(rows: usize, cols: usize, spacing: f32) -> Self {
+        Self {
+            walls: vec![],
+            sensors: vec![],
+            joints: vec![],
+            gravity: Vector2::zero(),
+            solver_iterations: 8,
+            resting_speed: 0.5,
+            position_correction: PositionCorrection::default(),
+            sleep_speed: 0.05,
+            time_to_sleep: 1.,
+            draw_grid: true,
+            blocks: Arena::new(),
+            broad_phase: BroadPhase::new(rows, cols, spacing),
+            contacts: ContactTracker::default(),
+            contact_hook: None,
+            metrics: StepMetrics::default(),
+            events: StepEvents::default(),
+            scratch: Scratch::default(),
+        }
+    }
+
+    #[must_use]
+    pub fn snapshot(&self) -> Snapshot {
+        let grid = self.broad_phase.grid();
+        Snapshot {
+            blocks: self.blocks.clone(),
+            walls: self.walls.clone(),
+            sensors: self.sensors.clone(),
+            joints: self.joints.clone(),
+            gravity: self.gravity,
+            contacts: self.contacts.clone(),
+            rows: grid.rows(),
+            cols: grid.cols(),
+            spacing: grid.spacing,
+        }
+    }
+
+    // Same as `snapshot` reusing the memory of an older one
+    pub fn snapshot_into(&self, snapshot: &mut Snapshot) {
+        let grid = self.broad_phase.grid();
+        snapshot.blocks.clone_from(&self.blocks);
+        snapshot.walls.clone_from(&self.walls);
+        snapshot.sensors.clone_from(&self.sensors);
+        snapshot.joints.clone_from(&self.joints);
+        snapshot.gravity = self.gravity;
+        snapshot.contacts.clone_from(&self.contacts);
+        snapshot.rows = grid.rows();
+        snapshot.cols = grid.cols();
+        snapshot.spacing = grid.spacing;
+    }
+
+    // Handles taken before the snapshot work again after restoring it
+    pub fn restore(&mut self, snapshot: &Snapshot) {
+        self.blocks.clone_from(&snapshot.blocks);
+        self.walls.clone_from(&snapshot.walls);
+        self.sensors.clone_from(&snapshot.sensors);
+        self.joints.clone_from(&snapshot.joints);
+        self.gravity = snapshot.gravity;
+        self.contacts.clone_from(&snapshot.contacts);
+
+        let grid = self.broad_phase.grid();
+        if (grid.rows(), grid.cols(), grid.spacing)
+            != (snapshot.rows, snapshot.cols, snapshot.spacing)
+        {
+            self.broad_phase = BroadPhase::new(snapshot.rows, snapshot.cols, snapshot.spacing);
+        }
+        self.update_grid();
+    }
+
+    pub fn set_contact_hook<H: ContactHook + 'static>(&mut self, hook: H) {
+        self.contact_hook = Some(Box::new(hook));
+    }
+
+    pub fn add_block(&mut self, mut block: Block) -> Handle {
+        self.blocks.insert_with(|handle| {
+            block.set_handle(handle);
+            block
+        })
+    }
+
+    // The handle stops working, joints using it are ignored.
+    // Sleeping blocks touching or joined to the removed one wake up instead of floating.
+    pub fn remove_block(&mut self, handle: Handle) -> Option<Block> {
+        let block = self.blocks.remove(handle)?;
+        let key = GridKey::Block(handle);
+        self.broad_phase.remove(key);
+
+        let touching = self
+            .contacts
+            .touching()
+            .iter()
+            .filter_map(|&(a, b)| match (a, b) {
+                (GridKey::Block(other), _) if b == key => Some(other),
+                (_, GridKey::Block(other)) if a == key => Some(other),
+                _ => None,
+            });
+        let reported = self
+            .scratch
+            .contacts
+            .get(&handle)
+            .into_iter()
+            .flatten()
+            .filter_map(|contact| contact.other);
+        let joined = self.joints.iter().filter_map(|joint| match joint.other() {
+            Some(other) if joint.body == handle => Some(other),
+            Some(other) if other == handle => Some(joint.body),
+            _ => None,
+        });
+        for other in touching.chain(reported).chain(joined) {
+            if let Some(other) = self.blocks.get_mut(other) {
+                other.wake();
+            }
+        }
+        Some(block)
+    }
+
+    #[must_use]
+    pub fn blocks(&self) -> &Arena<Block> {
+        &self.blocks
+    }
+
+    #[must_use]
+    pub fn block(&self, handle: Handle) -> Option<&Block> {
+        self.blocks.get(handle)
+    }
+
+    pub fn block_mut(&mut self, handle: Handle) -> Option<&mut Block> {
+        self.blocks.get_mut(handle)
+    }
+
+    #[must_use]
+    pub fn bodies(&self) -> Bodies<'_> {
+        Bodies {
+            blocks: &self.blocks,
+            walls: &self.walls,
+            sensors: &self.sensors,
+        }
+    }
+
+    #[must_use]
+    pub fn metrics(&self) -> &StepMetrics {
+        &self.metrics
+    }
+
+    // Up to date with the bodies as of the start of the last step
+    #[must_use]
+    pub fn grid(&self) -> &Grid<GridKey> {
+        self.broad_phase.grid()
+    }
+
+    // Moves the bodies that changed cells since the last step, called by `step`
+    pub fn update_grid(&mut self) {
+        self.broad_phase.update(Bodies {
+            blocks: &self.blocks,
+            walls: &self.walls,
+            sensors: &self.sensors,
+        });
+    }
+
+    // The events stay available until the next step
+    pub fn step(&mut self, delta: f32) -> &StepEvents {
+        let start = Instant::now();
+        // Gravity speeds up resting blocks every step, only the ones already moving wake others
+        let mut moving = std::mem::take(&mut self.scratch.moving);
+        moving.clear();
+        moving.extend(
+            self.blocks
+                .iter()
+                .filter(|(_, block)| block.speed().length() > self.sleep_speed)
+                .map(|(handle, _)| handle),
+        );
+        self.apply_springs(delta);
+        for block in self
+            .blocks
+            .values_mut()
+            .filter(|block| !block.is_sleeping())
+        {
+            block.apply_force(self.gravity * block.mass());
+            block.integrate_forces(delta);
+        }
+
+        let mut update = start.elapsed();
+
+        self.find_contacts();
+
+        let start = Instant::now();
+        self.find_overlaps();
+        self.metrics.narrow_phase += start.elapsed();
+
+        let start = Instant::now();
+        let contacts = std::mem::take(&mut self.scratch.contacts);
+        // Before waking anyone, the sleeping blocks are the ones that didn't look for contacts
+        let pairs = contacts.iter().flat_map(|(&handle, contacts)| {
+            contacts
+                .iter()
+                .filter_map(move |contact| contact.key.map(|key| (GridKey::Block(handle), key)))
+        });
+        let blocks = &self.blocks;
+        let asleep = |key| match key {
+            GridKey::Block(handle) => blocks.get(handle).is_some_and(Block::is_sleeping),
+            GridKey::Wall(_) | GridKey::Sensor(_) => true,
+        };
+        self.events.contacts.clear();
+        self.contacts
+            .update_keeping(pairs, asleep, &mut self.events.contacts);
+        self.wake_touched(&contacts, &moving);
+
+        self.pair_contacts(&contacts);
+        self.solve(&contacts);
+        self.blocks.values_mut().for_each(Block::update);
+        self.correct_positions(&contacts);
+        self.walls.iter_mut().for_each(|wall| wall.update(delta));
+        for block in self.blocks.values_mut() {
+            block.update_sleep(delta, self.sleep_speed, self.time_to_sleep);
+        }
+
+        self.events.sensors.clear();
+        let Scratch {
+            overlaps,
+            overlap_ranges,
+            ..
+        } = &self.scratch;
+        for (sensor, range) in self.sensors.iter_mut().zip(overlap_ranges) {
+            sensor.update(&overlaps[range.clone()], &mut self.events.sensors);
+        }
+        self.scratch.contacts = contacts;
+        self.scratch.moving = moving;
+        update += start.elapsed();
+
+        self.metrics.update = update;
+        &self.events
+    }
+}
+
+impl World {
+    // Grid, broad and narrow phase of the blocks, every block gets its list in `scratch.contacts`.
+    // Once the buffers are large enough this doesn't allocate.
+    fn find_contacts(&mut self) {
+        let start = Instant::now();
+        self.update_grid();
+        self.metrics.grid_build = start.elapsed();
+
+        let start = Instant::now();
+        let bodies = Bodies {
+            blocks: &self.blocks,
+            walls: &self.walls,
+            sensors: &self.sensors,
+        };
+        let grid = self.broad_phase.grid();
+        let Scratch {
+            keys,
+            candidates,
+            ranges,
+            contacts,
+            ..
+        } = &mut self.scratch;
+        candidates.clear();
+        ranges.clear();
+        for (handle, block) in self.blocks.iter() {
+            block.contact_candidates(grid, bodies, keys);
+            let start = candidates.len();
+            candidates.extend_from_slice(keys);
+            ranges.push((handle, start..candidates.len()));
+        }
+        self.metrics.broad_phase = start.elapsed();
+
+        let start = Instant::now();
+        let mut counts = NarrowPhaseCounts::default();
+        contacts.retain(|&handle, _| self.blocks.contains(handle));
+        for (handle, range) in ranges.iter() {
+            let items = candidates[range.clone()]
+                .iter()
+                .filter_map(|&key| Some((key, bodies.get(key)?)));
+            let list = contacts.entry(*handle).or_default();
+            list.clear();
+            let block =
+                self.blocks[*handle].contacts_with(items, self.contact_hook.as_deref(), list);
+            counts.tested_pairs += block.tested_pairs;
+            counts.collision_checks += block.collision_checks;
+        }
+        self.metrics.narrow_phase = start.elapsed();
+
+        self.metrics.candidate_pairs = candidates.len();
+        self.metrics.tested_pairs = counts.tested_pairs;
+        self.metrics.collision_checks = counts.collision_checks;
+    }
+
+    // Bodies overlapping every sensor and blocks touching the moving walls, in `scratch`
+    fn find_overlaps(&mut self) {
+        let bodies = Bodies {
+            blocks: &self.blocks,
+            walls: &self.walls,
+            sensors: &self.sensors,
+        };
+        let grid = self.broad_phase.grid();
+        let Scratch {
+            keys,
+            overlaps,
+            overlap_ranges,
+            touched,
+            ..
+        } = &mut self.scratch;
+        overlaps.clear();
+        overlap_ranges.clear();
+        for sensor in &self.sensors {
+            let start = overlaps.len();
+            sensor.calculate_overlaps(grid, bodies, keys, overlaps);
+            overlap_ranges.push(start..overlaps.len());
+        }
+
+        touched.clear();
+        for wall in self
+            .walls
+            .iter()
+            .filter(|wall| wall.body_kind() == BodyKind::Kinematic)
+        {
+            let (rows, cols) = wall.calculate_grid_ranges(grid.spacing);
+            keys.clear();
+            keys.extend(grid.iter_many(rows, cols));
+            keys.sort_unstable();
+            keys.dedup();
+            touched.extend(
+                keys.iter()
+                    .filter_map(|&key| bodies.block(key))
+                    .filter(|block| {
+                        block
+                            .shapes()
+                            .any(|shape| Manifold::between(wall, &shape).is_some())
+                    })
+                    .filter_map(GridItemTrait::handle),
+            );
+        }
+    }
+
+    // Sleeping blocks don't look for contacts, the moving bodies touching them wake them up
+    fn wake_touched(&mut self, contacts: &HashMap<Handle, Vec<Contact>>, moving: &HashSet<Handle>) {
+        let joined = self
+            .joints
+            .iter()
+            .filter_map(|joint| {
+                joint
+                    .other()
+                    .map(|other| [(joint.body, other), (other, joint.body)])
+            })
+            .flatten()
+            .filter(|(handle, _)| moving.contains(handle))
+            .map(|(_, other)| other);
+        let woken = moving
+            .iter()
+            .filter_map(|handle| contacts.get(handle))
+            .flat_map(|contacts| contacts.iter().filter_map(|contact| contact.other))
+            .chain(self.scratch.touched.iter().copied())
+            .chain(joined);
+
+        for handle in woken {
+            if let Some(block) = self
+                .blocks
+                .get_mut(handle)
+                .filter(|block| block.is_sleeping())
+            {
+                block.wake();
+            }
+        }
+    }
+
+    fn apply_springs(&mut self, delta: f32) {
+        for joint in &self.joints {
+            with_pair(
+                &mut self.blocks,
+                joint.body,
+                joint.other(),
+                |block, other| {
+                    let force = joint.spring_force(block, other.as_deref(), delta);
+                    if !block.is_sleeping() {
+                        block.apply_force(force);
+                    }
+                    if let Some(other) = other.filter(|other| !other.is_sleeping()) {
+                        other.apply_force(-force);
+                    }
+                },
+            );
+        }
+    }
+
+    // Pairs between blocks are usually reported by both of them, only the lower handle one is kept.
+    // They go to `scratch.pairs` with the index of the contact in the list of the first block.
+    fn pair_contacts(&mut self, contacts: &HashMap<Handle, Vec<Contact>>) {
+        let pairs = &mut self.scratch.pairs;
+        pairs.clear();
+        pairs.extend(
+            contacts
+                .iter()
+                .flat_map(|(&handle, contacts)| {
+                    contacts
+                        .iter()
+                        .enumerate()
+                        .map(move |(index, contact)| (handle, index, contact))
+                })
+                .filter_map(|(handle, index, contact)| {
+                    let other = contact.other.filter(|&other| self.blocks.contains(other));
+                    let reported = |other| {
+                        contacts[&other]
+                            .iter()
+                            .any(|contact| contact.other == Some(handle))
+                    };
+                    match other {
+                        Some(other) if other < handle && reported(other) => None,
+                        _ => Some((handle, other, index)),
+                    }
+                }),
+        );
+
+        // Solving in a stable order keeps the simulation deterministic
+        pairs.sort_unstable();
+    }
+
+    fn solve(&mut self, contacts: &HashMap<Handle, Vec<Contact>>) {
+        let Scratch {
+            pairs, constraints, ..
+        } = &mut self.scratch;
+        constraints.clear();
+        constraints.extend(pairs.iter().map(|&(handle, other, index)| {
+            let constraint = ContactConstraint::new(
+                &contacts[&handle][index],
+                &self.blocks[handle],
+                other.map(|other| &self.blocks[other]),
+                self.resting_speed,
+            );
+            (handle, other, constraint)
+        }));
+
+        for _ in 0..self.solver_iterations {
+            for (handle, other, constraint) in constraints.iter_mut() {
+                with_pair(&mut self.blocks, *handle, *other, |block, other| {
+                    constraint.apply(block, other);
+                });
+            }
+            for joint in &self.joints {
+                with_pair(
+                    &mut self.blocks,
+                    joint.body,
+                    joint.other(),
+                    |block, other| {
+                        joint.apply(block, other);
+                    },
+                );
+            }
+        }
+    }
+
+    fn correct_positions(&mut self, contacts: &HashMap<Handle, Vec<Contact>>) {
+        for &(handle, other, index) in &self.scratch.pairs {
+            let contact = &contacts[&handle][index];
+            with_pair(&mut self.blocks, handle, other, |block, other| {
+                self.position_correction
+                    .apply(block, other, &contact.manifold);
+            });
+        }
+    }
+}
+
+impl Draw for World {
+    fn draw(&self, canvas: &mut impl RaylibDraw) {
+        self.blocks.values().for_each(|block| block.draw(canvas));
+        self.walls.iter().for_each(|wall| wall.draw(canvas));
+        self.sensors.iter().for_each(|sensor| sensor.draw(canvas));
+
+        for joint in &self.joints {
+            let Some(block) = self.blocks.get(joint.body) else {
+                continue;
+            };
+            let other = joint.other().and_then(|other| self.blocks.get(other));
+            if joint.other().is_some() && other.is_none() {
+                continue;
+            }
+            let end = joint.anchor_position(other);
+            canvas.draw_line_v(block.center(), end, Color::DARKGRAY);
+        }
+
+        if self.draw_grid {
+            self.broad_phase.grid().draw(canvas);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        alloc::{GlobalAlloc, Layout, System},
+        cell::Cell,
+    };
+
+    use raylib::math::{Rectangle, Vector2};
+
+    use crate::{
+        collision::{
+            contact::Friction,
+            joint::{JointAnchor, JointKind},
+        },
+        entity::wall::WallMotion,
+        traits::Position,
+    };
+
+    use super::*;
+
+    // Counts the allocations of each thread, the other tests run in parallel
+    struct CountingAllocator;
+
+    thread_local! {
+        static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+    }
+
+    unsafe impl GlobalAlloc for CountingAllocator {
+        unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+            let _ = ALLOCATIONS.try_with(|count| count.set(count.get() + 1));
+            unsafe { System.alloc(layout) }
+        }
+
+        unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+            unsafe { System.dealloc(ptr, layout) }
+        }
+    }
+
+    #[global_allocator]
+    static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+    fn allocations() -> usize {
+        ALLOCATIONS.with(Cell::get)
+    }
+
+    // A block pushed to the right reaches the wall on the first step and bounces on the second
+    fn hit_wall<H: ContactHook + 'static>(hook: H) -> f32 {
+        let mut world = World::new(10, 10, 10.);
+        world.walls = vec![Wall::new(Vector2::new(50., 0.), Vector2::new(50., 100.))];
+        let block = world.add_block(Block::new(
+            Rectangle::new(38., 20., 5., 5.),
+            Vector2::new(800., 0.),
+        ));
+        world.set_contact_hook(hook);
+        world.position_correction.factor = 0.;
+
+        (0..2).for_each(|_| {
+            world.step(0.1);
+        });
+
+        world.blocks[block].position().x
+    }
+
+    #[test]
+    fn test_contact_hook_changes_restitution() {
+        let bounce = |_: &Block, _: &dyn GridItemTrait, _: &mut Contact| true;
+        let soft = |_: &Block, _: &dyn GridItemTrait, contact: &mut Contact| {
+            contact.restitution = 0.5;
+            true
+        };
+
+        assert!((hit_wall(bounce) - 38.).abs() < f32::EPSILON);
+        assert!((hit_wall(soft) - 42.).abs() < f32::EPSILON);
+    }
+
+    #[test]
+    fn test_contact_hook_ignores_and_one_way() {
+        let ignore = |_: &Block, _: &dyn GridItemTrait, _: &mut Contact| false;
+        let one_way = |_: &Block, _: &dyn GridItemTrait, contact: &mut Contact| {
+            contact.one_way = Some(Vector2::new(1., 0.));
+            true
+        };
+
+        assert!((hit_wall(ignore) - 54.).abs() < f32::EPSILON);
+        assert!((hit_wall(one_way) - 54.).abs() < f32::EPSILON);
+    }
+
+    #[test]
+    fn test_head_on_conserves_momentum() {
+        let mut world = World::new(10, 10, 10.);
+        let mut light = Block::new(Rectangle::new(10., 20., 10., 10.), Vector2::zero());
+        let mut heavy = Block::new(Rectangle::new(40., 20., 10., 10.), Vector2::zero());
+        light.set_mass(1.);
+        heavy.set_mass(3.);
+        light.set_speed(Vector2::new(5., 0.));
+        heavy.set_speed(Vector2::new(-3., 0.));
+        let light = world.add_block(light);
+        let heavy = world.add_block(heavy);
+
+        let momentum = |world: &World| {
+            world.blocks().values().fold(Vector2::zero(), |acc, block| {
+                acc + block.speed() * block.mass()
+            })
+        };
+        let before = momentum(&world);
+
+        let mut collided = false;
+        for _ in 0..10 {
+            let events = world.step(0.1);
+            collided |= events.contacts.contains(&ContactEvent::Begin(
+                GridKey::Block(light),
+                GridKey::Block(heavy),
+            ));
+        }
+
+        assert!(collided);
+        assert!(world.blocks[light].speed().x < 0.);
+        assert!((momentum(&world) - before).length() < 1e-4);
+    }
+
+    #[test]
+    fn test_gravity_and_forces() {
+        let mut world = World::new(10, 10, 10.);
+        world.gravity = Vector2::new(0., 100.);
+        let block = world.add_block(Block::new(
+            Rectangle::new(10., 10., 2., 2.),
+            Vector2::zero(),
+        ));
+
+        world.step(0.1);
+        world.step(0.1);
+        assert!((world.blocks[block].speed().y - 2.).abs() < 1e-5);
+
+        // A force only lasts for the next step
+        world.gravity = Vector2::zero();
+        world.blocks[block].apply_force(Vector2::new(400., 0.));
+        world.step(0.1);
+        world.step(0.1);
+        assert!((world.blocks[block].speed().x - 1.).abs() < 1e-5);
+
+        world.blocks[block].apply_impulse(Vector2::new(-4., -4.));
+        world.blocks[block].set_damping(10.);
+        world.step(0.1);
+        assert!(world.blocks[block].speed().x.abs() < 1e-5);
+        assert!((world.blocks[block].speed().y - 0.5).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_stack_comes_to_rest() {
+        let mut world = World::new(20, 20, 10.);
+        world.gravity = Vector2::new(0., 500.);
+        world.walls = vec![Wall::new(Vector2::new(0., 150.), Vector2::new(200., 150.))];
+        for index in 0..3 {
+            #[allow(clippy::cast_precision_loss)]
+            let y = 130. - index as f32 * 15.;
+            let mut block = Block::new(Rectangle::new(90., y, 10., 10.), Vector2::zero());
+            block.set_restitution(0.);
+            block.set_friction(Friction::new(0.6, 0.4));
+            world.add_block(block);
+        }
+
+        let positions = |world: &World| {
+            world
+                .blocks()
+                .values()
+                .map(Position::position)
+                .collect::<Vec<_>>()
+        };
+        for _ in 0..600 {
+            world.step(1. / 60.);
+        }
+        let settled = positions(&world);
+        for _ in 0..60 {
+            world.step(1. / 60.);
+        }
+
+        let floor = 147.5;
+        for (index, (before, after)) in settled.iter().zip(positions(&world)).enumerate() {
+            #[allow(clippy::cast_precision_loss)]
+            let resting = floor - 10. * (index + 1) as f32;
+            assert!((*before - after).length() < 1e-3);
+            assert!((after.y - resting).abs() < 1.);
+            assert!((after.x - 90.).abs() < 1e-3);
+        }
+    }
+
+    #[test]
+    fn test_kinematic_pushes_without_being_pushed() {
+        let mut world = World::new(20, 20, 10.);
+        world.gravity = Vector2::new(0., 500.);
+        world.position_correction.factor = 0.;
+
+        let mut platform = Block::new(Rectangle::new(40., 100., 60., 10.), Vector2::zero());
+        platform.set_body_kind(BodyKind::Kinematic);
+        platform.set_speed(Vector2::new(0., -1.));
+        let mut anchor = Block::new(Rectangle::new(150., 50., 10., 10.), Vector2::zero());
+        anchor.set_body_kind(BodyKind::Static);
+        let mut block = Block::new(Rectangle::new(60., 90., 10., 10.), Vector2::zero());
+        block.set_restitution(0.);
+        let handles = [platform, block, anchor].map(|block| world.add_block(block));
+
+        for _ in 0..30 {
+            world.step(1. / 60.);
+        }
+
+        let [platform, block, anchor] = handles.map(|handle| &world.blocks[handle]);
+        assert_eq!(platform.speed(), Vector2::new(0., -1.));
+        assert!((platform.position().y - 70.).abs() < 1e-3);
+        assert!(block.position().y < 62.);
+        assert!((block.speed().y + 1.).abs() < 1e-3);
+        assert_eq!(anchor.position(), Vector2::new(150., 50.));
+    }
+
+    #[test]
+    fn test_moving_wall_carries_blocks() {
+        let mut world = World::new(20, 20, 10.);
+        world.gravity = Vector2::new(0., 500.);
+        let mut elevator = Wall::new(Vector2::new(0., 150.), Vector2::new(200., 150.));
+        elevator.set_motion(Some(WallMotion {
+            speed: Vector2::new(30., -60.),
+            ..WallMotion::default()
+        }));
+        elevator.set_friction(Friction::new(1., 1.));
+        world.walls = vec![elevator];
+        let mut block = Block::new(Rectangle::new(90., 137., 10., 10.), Vector2::zero());
+        block.set_restitution(0.);
+        block.set_friction(Friction::new(1., 1.));
+        let block = world.add_block(block);
+
+        for _ in 0..60 {
+            world.step(1. / 60.);
+        }
+
+        let speed = world.blocks[block].speed();
+        assert!((speed.x - 0.5).abs() < 0.05);
+        assert!((speed.y + 1.).abs() < 0.05);
+        assert!((world.blocks[block].position().y - 77.5).abs() < 1.);
+    }
+
+    #[test]
+    fn test_jump_through_one_way_platform() {
+        let mut world = World::new(20, 20, 10.);
+        world.gravity = Vector2::new(0., 500.);
+        let mut platform = Wall::new(Vector2::new(0., 100.), Vector2::new(200., 100.));
+        platform.set_one_way(true);
+        world.walls = vec![platform];
+        let mut block = Block::new(Rectangle::new(90., 120., 10., 10.), Vector2::zero());
+        block.set_restitution(0.);
+        block.set_speed(Vector2::new(0., -4.));
+        let block = world.add_block(block);
+
+        for _ in 0..120 {
+            world.step(1. / 60.);
+        }
+
+        let block = &world.blocks[block];
+        assert!(block.speed().length() < 1e-3);
+        assert!((block.position().y - 87.5).abs() < 0.5);
+    }
+
+    #[test]
+    fn test_sleeping() {
+        let mut world = World::new(20, 20, 10.);
+        world.gravity = Vector2::new(0., 500.);
+        let mut floor = Wall::new(Vector2::new(0., 150.), Vector2::new(200., 150.));
+        floor.set_friction(Friction::new(0.5, 0.5));
+        world.walls = vec![floor];
+        let mut resting = Block::new(Rectangle::new(90., 137.5, 10., 10.), Vector2::zero());
+        resting.set_restitution(0.);
+        resting.set_friction(Friction::new(0.5, 0.5));
+        let resting = world.add_block(resting);
+
+        for _ in 0..90 {
+            world.step(1. / 60.);
+        }
+        assert!(world.blocks[resting].is_sleeping());
+
+        // Hit by a moving block
+        let mut moving = Block::new(Rectangle::new(60., 137.5, 10., 10.), Vector2::zero());
+        moving.set_friction(Friction::new(0.5, 0.5));
+        moving.set_speed(Vector2::new(2., 0.));
+        let moving = world.add_block(moving);
+        let position = world.blocks[resting].position();
+        for _ in 0..20 {
+            world.step(1. / 60.);
+        }
+        assert!(!world.blocks[resting].is_sleeping());
+        assert!(world.blocks[resting].position().x > position.x);
+
+        // Woken by a force
+        for _ in 0..300 {
+            world.step(1. / 60.);
+        }
+        assert!(world.blocks().values().all(Block::is_sleeping));
+        world.blocks[moving].apply_force(Vector2::new(-1000., 0.));
+        assert!(!world.blocks[moving].is_sleeping());
+        assert!(world.blocks[resting].is_sleeping());
+    }
+
+    #[test]
+    fn test_resting_stack_keeps_touching_asleep() {
+        let mut world = World::new(20, 20, 10.);
+        world.gravity = Vector2::new(0., 500.);
+        world.walls = vec![Wall::new(Vector2::new(0., 150.), Vector2::new(200., 150.))];
+        for y in [137.5, 127.5] {
+            let mut block = Block::new(Rectangle::new(90., y, 10., 10.), Vector2::zero());
+            block.set_restitution(0.);
+            world.add_block(block);
+        }
+
+        let mut ended = false;
+        for _ in 0..180 {
+            let events = world.step(1. / 60.);
+            ended |= events
+                .contacts
+                .iter()
+                .any(|event| matches!(event, ContactEvent::End(..)));
+        }
+
+        assert!(world.blocks().values().all(Block::is_sleeping));
+        assert!(!ended);
+        assert_eq!(world.contacts.touching().len(), 2);
+    }
+
+    #[test]
+    fn test_pendulum_keeps_its_length() {
+        let mut world = World::new(20, 20, 10.);
+        world.gravity = Vector2::new(0., 500.);
+        let block = world.add_block(Block::new(
+            Rectangle::new(145., 45., 10., 10.),
+            Vector2::zero(),
+        ));
+        let pivot = Vector2::new(100., 50.);
+        world.joints = vec![Joint::new(
+            block,
+            JointAnchor::Point(pivot),
+            JointKind::Distance { length: 50. },
+        )];
+
+        let mut lowest = 0_f32;
+        for _ in 0..60 {
+            world.step(1. / 60.);
+            let center = world.blocks[block].center();
+            assert!((center.distance_to(pivot) - 50.).abs() < 1e-2);
+            lowest = lowest.max(center.y);
+        }
+        assert!(lowest > 99.);
+    }
+
+    #[test]
+    fn test_chain_and_spring() {
+        let mut world = World::new(20, 20, 10.);
+        world.gravity = Vector2::new(0., 500.);
+        let [first, second, third] = [20., 40., 60.].map(|x| {
+            let mut block = Block::new(Rectangle::new(x, 20., 5., 5.), Vector2::zero());
+            block.set_damping(2.);
+            world.add_block(block)
+        });
+        world.joints = vec![
+            Joint::pin(first, Vector2::new(22.5, 22.5)),
+            Joint::new(
+                first,
+                JointAnchor::Body(second),
+                JointKind::Rope { length: 20. },
+            ),
+            Joint::new(
+                second,
+                JointAnchor::Body(third),
+                JointKind::Spring {
+                    length: 20.,
+                    stiffness: 500.,
+                    damping: 50.,
+                },
+            ),
+        ];
+
+        for _ in 0..600 {
+            world.step(1. / 60.);
+        }
+
+        let centers = [first, second, third].map(|handle| world.blocks[handle].center());
+        assert!((centers[0] - Vector2::new(22.5, 22.5)).length() < 1e-2);
+        assert!((centers[1] - Vector2::new(22.5, 42.5)).length() < 0.5);
+        // Hanging from the spring, stretched by its weight
+        let stretch = 25. * 500. / 500.;
+        assert!((centers[2].y - (62.5 + stretch)).abs() < 0.5);
+    }
+
+    #[test]
+    fn test_removed_block_handle_is_stale() {
+        let mut world = World::new(10, 10, 10.);
+        let [first, second] = [10., 40.].map(|x| {
+            world.add_block(Block::new(
+                Rectangle::new(x, 20., 10., 10.),
+                Vector2::zero(),
+            ))
+        });
+        world.joints = vec![Joint::new(
+            first,
+            JointAnchor::Body(second),
+            JointKind::Distance { length: 10. },
+        )];
+
+        assert!(world.remove_block(second).is_some());
+        assert!(world.block(second).is_none());
+        assert!(world.remove_block(second).is_none());
+
+        // The slot is reused but the old handle doesn't reach the new block
+        let third = world.add_block(Block::new(
+            Rectangle::new(70., 20., 10., 10.),
+            Vector2::zero(),
+        ));
+        assert_eq!(third.index(), second.index());
+        assert_eq!(world.block(third).and_then(Block::handle), Some(third));
+        assert!(world.block(second).is_none());
+
+        // Joints with removed bodies are skipped
+        world.step(0.1);
+        assert_eq!(world.blocks[first].position(), Vector2::new(10., 20.));
+        assert_eq!(world.blocks[third].position(), Vector2::new(70., 20.));
+    }
+
+    #[test]
+    fn test_removing_support_wakes_sleeping_blocks() {
+        let mut world = World::new(20, 20, 10.);
+        world.gravity = Vector2::new(0., 500.);
+        world.walls = vec![Wall::new(Vector2::new(0., 150.), Vector2::new(200., 150.))];
+        let [bottom, top] = [137.5, 127.5].map(|y| {
+            let mut block = Block::new(Rectangle::new(90., y, 10., 10.), Vector2::zero());
+            block.set_restitution(0.);
+            world.add_block(block)
+        });
+        let mut hanging = Block::new(Rectangle::new(150., 100., 10., 10.), Vector2::zero());
+        hanging.set_body_kind(BodyKind::Static);
+        let hanging = world.add_block(hanging);
+        let pendulum = world.add_block(Block::new(
+            Rectangle::new(150., 120., 10., 10.),
+            Vector2::zero(),
+        ));
+        world.joints = vec![Joint::new(
+            pendulum,
+            JointAnchor::Body(hanging),
+            JointKind::Distance { length: 20. },
+        )];
+        for _ in 0..180 {
+            world.step(1. / 60.);
+        }
+        assert!(world.blocks[top].is_sleeping());
+        assert!(world.blocks[pendulum].is_sleeping());
+
+        world.remove_block(bottom);
+        world.remove_block(hanging);
+        assert!(!world.blocks[top].is_sleeping());
+        assert!(!world.blocks[pendulum].is_sleeping());
+        let y = world.blocks[top].position().y;
+        world.step(1. / 60.);
+        assert!(world.blocks[top].position().y > y);
+    }
+
+    #[test]
+    fn test_contained_block_is_pushed_out() {
+        let mut world = World::new(10, 10, 10.);
+        let outer = world.add_block(Block::new(
+            Rectangle::new(30., 30., 40., 40.),
+            Vector2::zero(),
+        ));
+        let inner = world.add_block(Block::new(
+            Rectangle::new(45., 48., 10., 10.),
+            Vector2::zero(),
+        ));
+
+        for _ in 0..60 {
+            world.step(1. / 60.);
+        }
+        let overlap = Manifold::between(&world.blocks[outer], &world.blocks[inner]);
+        let slop = world.position_correction.slop;
+        assert!(overlap.is_none_or(|overlap| overlap.depth < slop + 1e-3));
+    }
+
+    #[test]
+    fn test_wall_contact_events() {
+        let mut world = World::new(10, 10, 10.);
+        world.walls = vec![Wall::new(Vector2::new(50., 0.), Vector2::new(50., 100.))];
+        let mut block = Block::new(Rectangle::new(38., 20., 5., 5.), Vector2::zero());
+        block.set_speed(Vector2::new(4., 0.));
+        let block = GridKey::Block(world.add_block(block));
+        let wall = GridKey::Wall(0);
+
+        let events = (0..10)
+            .flat_map(|_| world.step(0.1).contacts.clone())
+            .collect::<Vec<_>>();
+        let begins = events
+            .iter()
+            .filter(|&&event| event == ContactEvent::Begin(wall, block))
+            .count();
+        assert_eq!(begins, 1);
+        assert!(events.contains(&ContactEvent::End(wall, block)));
+    }
+
+    #[test]
+    fn test_sensor_with_any_shape() {
+        let mut world = World::new(10, 10, 10.);
+        // A diagonal line, its bounding box covers the whole world
+        let line = Wall::new(Vector2::zero(), Vector2::new(100., 100.));
+        world.sensors = vec![Sensor::new(3, line).into_shared()];
+        let touching = world.add_block(Block::new(
+            Rectangle::new(45., 45., 10., 10.),
+            Vector2::zero(),
+        ));
+        world.add_block(Block::new(
+            Rectangle::new(70., 10., 10., 10.),
+            Vector2::zero(),
+        ));
+
+        let events = world.step(0.1);
+        assert_eq!(
+            events.sensors,
+            [SensorEvent::Enter {
+                sensor: 3,
+                body: touching
+            }]
+        );
+    }
+
+    #[test]
+    fn test_step_does_not_allocate() {
+        let mut world = World::new(20, 20, 10.);
+        world.gravity = Vector2::new(0., 500.);
+        world.time_to_sleep = f32::INFINITY;
+        // Spinning once per second, it goes through the same cells every 60 steps
+        let mut spinner = Wall::new(Vector2::new(10., 60.), Vector2::new(40., 60.));
+        spinner.set_motion(Some(WallMotion {
+            angular_speed: std::f32::consts::TAU,
+            pivot: Vector2::new(25., 60.),
+            ..WallMotion::default()
+        }));
+        world.walls = vec![
+            Wall::new(Vector2::new(0., 150.), Vector2::new(200., 150.)),
+            Wall::new(Vector2::new(52.5, 0.), Vector2::new(52.5, 150.)),
+            spinner,
+        ];
+        world.sensors = vec![Sensor::new(0, Rectangle::new(80., 100., 50., 50.)).into_shared()];
+        // Keeps swinging, some block is always moving
+        let pendulum = world.add_block(Block::new(
+            Rectangle::new(135., 15., 10., 10.),
+            Vector2::zero(),
+        ));
+        world.joints = vec![Joint::new(
+            pendulum,
+            JointAnchor::Point(Vector2::new(110., 20.)),
+            JointKind::Distance { length: 30. },
+        )];
+        for (x, y) in [(55., 135.), (91., 130.), (91., 115.), (121., 135.)] {
+            let mut block = Block::new(Rectangle::new(x, y, 10., 10.), Vector2::zero());
+            block.set_restitution(0.);
+            world.add_block(block);
+        }
+        let mut compound = Block::compound(
+            &[
+                Rectangle::new(150., 120., 10., 20.),
+                Rectangle::new(160., 130., 10., 10.),
+            ],
+            Vector2::zero(),
+        )
+        .unwrap();
+        compound.set_restitution(0.);
+        world.add_block(compound);
+
+        // The buffers grow during the first steps
+        for _ in 0..120 {
+            world.step(1. / 60.);
+        }
+
+        for _ in 0..60 {
+            let before = allocations();
+            world.step(1. / 60.);
+            assert_eq!(allocations() - before, 0);
+        }
+        let contacts = world.scratch.contacts.values().map(Vec::len).sum::<usize>();
+        assert!(contacts >= 6);
+        assert!(world.metrics().tested_pairs > 0);
+        assert!(world.metrics().collision_checks > 0);
+        assert_eq!(world.events.contacts.len(), 5);
+        assert!(world
+            .events
+            .contacts
+            .iter()
+            .all(|event| matches!(event, ContactEvent::Persist(..))));
+        assert_eq!(world.sensors[0].overlapping().len(), 3);
+        assert!(!world.scratch.moving.is_empty());
+    }
+
+    #[test]
+    fn test_snapshot_into_does_not_allocate() {
+        let mut world = World::new(20, 20, 10.);
+        world.gravity = Vector2::new(0., 500.);
+        world.walls = vec![Wall::new(Vector2::new(0., 150.), Vector2::new(200., 150.))];
+        world.sensors = vec![Sensor::new(0, Rectangle::new(0., 100., 200., 50.)).into_shared()];
+        world.joints = vec![Joint::pin(
+            world.add_block(Block::new(
+                Rectangle::new(20., 20., 10., 10.),
+                Vector2::zero(),
+            )),
+            Vector2::new(25., 25.),
+        )];
+        for x in [50., 90.] {
+            let mut block = Block::new(Rectangle::new(x, 130., 10., 10.), Vector2::zero());
+            block.set_restitution(0.);
+            world.add_block(block);
+        }
+        let mut compound = Block::compound(
+            &[
+                Rectangle::new(150., 120., 10., 20.),
+                Rectangle::new(160., 130., 10., 10.),
+            ],
+            Vector2::zero(),
+        )
+        .unwrap();
+        compound.set_restitution(0.);
+        world.add_block(compound);
+        for _ in 0..60 {
+            world.step(1. / 60.);
+        }
+
+        let mut snapshot = world.snapshot();
+        for _ in 0..10 {
+            world.step(1. / 60.);
+            let before = allocations();
+            world.snapshot_into(&mut snapshot);
+            assert_eq!(allocations() - before, 0);
+        }
+        assert_eq!(snapshot.contacts.touching().len(), 3);
+        assert_eq!(snapshot.sensors[0].overlapping().len(), 3);
+    }
+
+    #[test]
+    fn test_metrics() {
+        let mut world = World::new(10, 10, 10.);
+        // Two blocks overlapping and a third one next to them
+        for x in [10., 15., 30.] {
+            world.add_block(Block::new(
+                Rectangle::new(x, 20., 10., 10.),
+                Vector2::zero(),
+            ));
+        }
+        let mut anchor = Block::new(Rectangle::new(60., 60., 10., 10.), Vector2::zero());
+        anchor.set_body_kind(BodyKind::Static);
+        world.add_block(anchor);
+        world.walls = vec![Wall::new(Vector2::new(50., 75.), Vector2::new(90., 75.))];
+        assert_eq!(world.metrics(), &StepMetrics::default());
+
+        // The static block finds the wall but nothing can move there
+        world.step(0.1);
+        let metrics = world.metrics();
+        assert_eq!(metrics.candidate_pairs, 7);
+        assert_eq!(metrics.tested_pairs, 6);
+        assert_eq!(metrics.collision_checks, 2);
+    }
+
+    #[test]
+    fn test_snapshot_restore() {
+        let mut world = World::new(10, 10, 20.);
+        world.gravity = Vector2::new(0., 400.);
+        world.walls = vec![Wall::new(Vector2::new(0., 150.), Vector2::new(200., 150.))];
+        let handles = [20., 34., 48.].map(|x| {
+            let mut block = Block::new(Rectangle::new(x, 100. - x, 10., 10.), Vector2::zero());
+            block.set_speed(Vector2::new(1., 0.));
+            world.add_block(block)
+        });
+        for _ in 0..20 {
+            world.step(1. / 60.);
+        }
+
+        let snapshot = world.snapshot();
+        let state = |world: &World| {
+            handles.map(|handle| {
+                let block = &world.blocks[handle];
+                (block.position(), block.speed())
+            })
+        };
+        for _ in 0..40 {
+            world.step(1. / 60.);
+        }
+        let expected = state(&world);
+
+        world.remove_block(handles[0]);
+        world.walls.clear();
+        world.restore(&snapshot);
+        assert_eq!(world.blocks().len(), 3);
+        assert_eq!(world.walls.len(), 1);
+        for _ in 0..40 {
+            world.step(1. / 60.);
+        }
+        assert_eq!(state(&world), expected);
+
+        // Snapshots can be reused and restored in a world with another grid
+        let mut other = World::new(1, 1, 1.);
+        let mut reused = other.snapshot();
+        world.snapshot_into(&mut reused);
+        other.restore(&reused);
+        assert_eq!(other.grid().rows(), 10);
+        assert_eq!(state(&other), expected);
+    }
+}